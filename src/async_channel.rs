@@ -0,0 +1,381 @@
+//! Ring-backed channel for async code, independent of any runtime.
+//!
+//! [`Sender::send`] and [`Receiver::recv`] return plain [`Future`]s that
+//! register their task's [`Waker`] and are woken when the other side makes
+//! progress, so they work with any executor.  The buffer's
+//! [`OverflowPolicy`] decides what a send does when it is full: `Reject`
+//! waits for room, `Overwrite` and `DropNewest` never wait.
+//!
+//! For Sink- and Stream-style code, [`Sender::poll_ready`] followed by
+//! [`Sender::try_send`] sends without a future, and [`Receiver::poll_recv`]
+//! receives.  [`block_on`] is a minimal executor for tests and simple
+//! programs.
+
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use crate::channel::{SendError, TrySendError};
+use crate::{OverflowPolicy, RingBuffer};
+
+struct State<A> {
+    ring_buffer: RingBuffer<A>,
+    senders: usize,
+    receivers: usize,
+    /// Tasks waiting for an element or for the last sender to go.
+    recv_wakers: Vec<Waker>,
+    /// Tasks waiting for room or for the last receiver to go.
+    send_wakers: Vec<Waker>,
+}
+
+fn register(wakers: &mut Vec<Waker>, waker: &Waker) {
+    if !wakers.iter().any(|w| w.will_wake(waker)) {
+        wakers.push(waker.clone());
+    }
+}
+
+fn wake_all(wakers: &mut Vec<Waker>) {
+    wakers.drain(..).for_each(Waker::wake);
+}
+
+struct Shared<A> {
+    state: Mutex<State<A>>,
+}
+
+impl<A> Shared<A> {
+    fn lock(&self) -> MutexGuard<'_, State<A>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Sending half of an async [`channel`].
+pub struct Sender<A> {
+    shared: Arc<Shared<A>>,
+}
+
+/// Receiving half of an async [`channel`].
+pub struct Receiver<A> {
+    shared: Arc<Shared<A>>,
+}
+
+/// Create a channel buffering up to `size` elements whose sends wait for
+/// room when it is full.  Panics if `size` is 0.
+pub fn channel<A>(size: usize) -> (Sender<A>, Receiver<A>) {
+    with_policy(size, OverflowPolicy::Reject)
+}
+
+/// Like [`channel`] but handles a full buffer according to `policy`.
+pub fn with_policy<A>(size: usize, policy: OverflowPolicy) -> (Sender<A>, Receiver<A>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            ring_buffer: crate::with_policy(size, policy),
+            senders: 1,
+            receivers: 1,
+            recv_wakers: Vec::new(),
+            send_wakers: Vec::new(),
+        }),
+    });
+    (Sender { shared: Arc::clone(&shared) }, Receiver { shared })
+}
+
+impl<A> Sender<A> {
+    /// Send `val`, resolving to the element the buffer evicted for it, if
+    /// any, or to an error once every receiver is gone.
+    pub fn send(&self, val: A) -> SendFuture<'_, A> {
+        SendFuture { sender: self, val: Some(val) }
+    }
+
+    /// Send `val` without waiting, resolving like [`Sender::send`] but
+    /// handing `val` back if a `Reject` buffer is full.
+    pub fn try_send(&self, val: A) -> Result<Option<A>, TrySendError<A>> {
+        let mut state = self.shared.lock();
+        if state.receivers == 0 {
+            return Err(TrySendError::Disconnected(val));
+        }
+        let pushed = state.ring_buffer.push_checked(val);
+        if pushed.is_ok() {
+            wake_all(&mut state.recv_wakers);
+        }
+        pushed.map_err(|full| TrySendError::Full(full.into_inner()))
+    }
+
+    /// Sink-style readiness: `Ready(Ok(()))` once a send would not have to
+    /// wait, `Ready(Err(_))` once every receiver is gone.
+    ///
+    /// Other senders may fill the buffer again before the following
+    /// [`Sender::try_send`], which then reports it as full.
+    pub fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), SendError<()>>> {
+        let mut state = self.shared.lock();
+        if state.receivers == 0 {
+            return Poll::Ready(Err(SendError(())));
+        }
+        if !state.ring_buffer.is_full() || state.ring_buffer.policy() != OverflowPolicy::Reject {
+            return Poll::Ready(Ok(()));
+        }
+        register(&mut state.send_wakers, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl<A> Receiver<A> {
+    /// Receive the oldest element, resolving to `None` once the buffer is
+    /// empty and every sender is gone.
+    pub fn recv(&self) -> RecvFuture<'_, A> {
+        RecvFuture { receiver: self }
+    }
+
+    /// Receive the oldest element without waiting.
+    pub fn try_recv(&self) -> Option<A> {
+        let mut state = self.shared.lock();
+        let val = state.ring_buffer.pop_front()?;
+        wake_all(&mut state.send_wakers);
+        Some(val)
+    }
+
+    /// Stream-style polling: `Ready(None)` marks the end of the stream.
+    pub fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<A>> {
+        let mut state = self.shared.lock();
+        if let Some(val) = state.ring_buffer.pop_front() {
+            wake_all(&mut state.send_wakers);
+            return Poll::Ready(Some(val));
+        }
+        if state.senders == 0 {
+            return Poll::Ready(None);
+        }
+        register(&mut state.recv_wakers, cx.waker());
+        Poll::Pending
+    }
+}
+
+/// Future returned by [`Sender::send`].
+pub struct SendFuture<'a, A> {
+    sender: &'a Sender<A>,
+    val: Option<A>,
+}
+
+// The value is moved out by `poll`, never pinned.
+impl<A> Unpin for SendFuture<'_, A> {}
+
+impl<A> Future for SendFuture<'_, A> {
+    type Output = Result<Option<A>, SendError<A>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let val = self.val.take().expect("SendFuture polled after completion");
+        let mut state = self.sender.shared.lock();
+        if state.receivers == 0 {
+            return Poll::Ready(Err(SendError(val)));
+        }
+        match state.ring_buffer.push_checked(val) {
+            Ok(evicted) => {
+                wake_all(&mut state.recv_wakers);
+                Poll::Ready(Ok(evicted))
+            }
+            Err(full) => {
+                register(&mut state.send_wakers, cx.waker());
+                drop(state);
+                self.val = Some(full.into_inner());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Future returned by [`Receiver::recv`].
+pub struct RecvFuture<'a, A> {
+    receiver: &'a Receiver<A>,
+}
+
+impl<A> Future for RecvFuture<'_, A> {
+    type Output = Option<A>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<A>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl<A> Clone for Sender<A> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Sender { shared: Arc::clone(&self.shared) }
+    }
+}
+
+impl<A> Clone for Receiver<A> {
+    fn clone(&self) -> Self {
+        self.shared.lock().receivers += 1;
+        Receiver { shared: Arc::clone(&self.shared) }
+    }
+}
+
+impl<A> Drop for Sender<A> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            wake_all(&mut state.recv_wakers);
+        }
+    }
+}
+
+impl<A> Drop for Receiver<A> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receivers -= 1;
+        if state.receivers == 0 {
+            wake_all(&mut state.send_wakers);
+        }
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run `future` to completion on the current thread, parking it while the
+/// future is pending.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Waker;
+    use std::time::Duration;
+
+    fn poll_once<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        future_poll(Pin::new(future))
+    }
+
+    fn future_poll<F: Future>(future: Pin<&mut F>) -> Poll<F::Output> {
+        future.poll(&mut Context::from_waker(Waker::noop()))
+    }
+
+    #[test]
+    fn send_then_recv() {
+        let (tx, rx) = channel(2);
+        block_on(async {
+            assert_eq!(tx.send(1).await, Ok(None));
+            assert_eq!(tx.send(2).await, Ok(None));
+            assert_eq!(rx.recv().await, Some(1));
+            assert_eq!(rx.recv().await, Some(2));
+        });
+        assert_eq!(rx.try_recv(), None);
+    }
+
+    #[test]
+    fn reject_sends_wait_for_room() {
+        let (tx, rx) = channel(1);
+        assert_eq!(tx.try_send(1), Ok(None));
+        let mut send = tx.send(2);
+        assert!(poll_once(&mut send).is_pending());
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(rx.try_recv(), Some(1));
+        assert_eq!(poll_once(&mut send), Poll::Ready(Ok(None)));
+        assert_eq!(rx.try_recv(), Some(2));
+    }
+
+    #[test]
+    fn overwrite_sends_never_wait() {
+        let (tx, rx) = with_policy(2, OverflowPolicy::Overwrite);
+        block_on(async {
+            for i in 0..2 {
+                assert_eq!(tx.send(i).await, Ok(None));
+            }
+            assert_eq!(tx.send(2).await, Ok(Some(0)));
+            assert_eq!(tx.send(3).await, Ok(Some(1)));
+        });
+        assert_eq!(rx.try_recv(), Some(2));
+        let (tx, rx) = with_policy(1, OverflowPolicy::DropNewest);
+        assert_eq!(block_on(tx.send(1)), Ok(None));
+        assert_eq!(block_on(tx.send(2)), Ok(None));
+        assert_eq!(rx.try_recv(), Some(1));
+    }
+
+    #[test]
+    fn recv_ends_when_senders_are_gone() {
+        let (tx, rx) = channel(2);
+        let tx2 = tx.clone();
+        block_on(tx.send(1)).unwrap();
+        let mut recv = rx.recv();
+        assert_eq!(future_poll(Pin::new(&mut recv)), Poll::Ready(Some(1)));
+        let mut recv = rx.recv();
+        assert!(future_poll(Pin::new(&mut recv)).is_pending());
+        drop((tx, tx2));
+        assert_eq!(future_poll(Pin::new(&mut recv)), Poll::Ready(None));
+    }
+
+    #[test]
+    fn pending_send_fails_when_receivers_are_gone() {
+        let (tx, rx) = channel(1);
+        block_on(tx.send(1)).unwrap();
+        let mut send = tx.send(2);
+        assert!(poll_once(&mut send).is_pending());
+        drop(rx);
+        assert_eq!(poll_once(&mut send), Poll::Ready(Err(SendError(2))));
+    }
+
+    #[test]
+    fn poll_ready_waits_for_room() {
+        let (tx, rx) = channel(1);
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(tx.poll_ready(&mut cx), Poll::Ready(Ok(())));
+        tx.try_send(1).unwrap();
+        assert!(tx.poll_ready(&mut cx).is_pending());
+        assert_eq!(rx.try_recv(), Some(1));
+        assert_eq!(tx.poll_ready(&mut cx), Poll::Ready(Ok(())));
+        drop(rx);
+        assert_eq!(tx.poll_ready(&mut cx), Poll::Ready(Err(SendError(()))));
+        assert_eq!(tx.try_send(2), Err(TrySendError::Disconnected(2)));
+    }
+
+    #[test]
+    fn tasks_on_different_threads_wake_each_other() {
+        let (tx, rx) = channel(3);
+        let producers: Vec<_> = (0..3)
+            .map(|p| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    block_on(async {
+                        for i in 0..500 {
+                            tx.send(p * 500 + i).await.unwrap();
+                        }
+                    })
+                })
+            })
+            .collect();
+        drop(tx);
+        let consumer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            block_on(async {
+                let mut total = 0;
+                while let Some(x) = rx.recv().await {
+                    total += x;
+                }
+                total
+            })
+        });
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(consumer.join().unwrap(), (0..1500).sum::<i32>());
+    }
+}
@@ -0,0 +1,23 @@
+/// Spin a little, then let other threads run, while waiting on a slot that
+/// another thread is in the middle of updating.
+pub(crate) struct Backoff(u32);
+
+impl Backoff {
+    pub(crate) fn new() -> Self {
+        Backoff(0)
+    }
+
+    pub(crate) fn snooze(&mut self) {
+        if self.0 < 6 {
+            for _ in 0..1 << self.0 {
+                core::hint::spin_loop();
+            }
+            self.0 += 1;
+        } else {
+            #[cfg(feature = "std")]
+            std::thread::yield_now();
+            #[cfg(not(feature = "std"))]
+            core::hint::spin_loop();
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Single-writer, multi-reader broadcast ring.
+//!
+//! Every [`Reader`] sees every element at its own pace by keeping its own
+//! absolute sequence number.  The [`Writer`] never waits for readers to
+//! catch up: it overwrites the oldest slot when the ring is full, and a
+//! reader that falls more than a capacity behind learns how many elements
+//! it missed through [`ReadError::Lagged`] before resuming at the oldest
+//! one left.
+//!
+//! Elements are stored behind an `Arc`.  Each slot has a lock, but it is
+//! only held to swap or take a reference to that `Arc`: readers clone the
+//! element after letting go of it, so the writer never waits on a clone.
+
+use std::error;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use alloc::boxed::Box;
+
+use crate::cache_padded::CachePadded;
+
+struct Slot<A> {
+    /// Sequence number of `val`, meaningless while `val` is `None`.
+    seq: u64,
+    val: Option<Arc<A>>,
+}
+
+struct Shared<A> {
+    slots: Box<[Mutex<Slot<A>>]>,
+    /// Sequence number the next write will get.
+    head: CachePadded<AtomicU64>,
+    readers: AtomicUsize,
+}
+
+impl<A> Shared<A> {
+    fn lock(&self, seq: u64) -> MutexGuard<'_, Slot<A>> {
+        let idx = (seq % self.slots.len() as u64) as usize;
+        self.slots[idx].lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn capacity(&self) -> u64 {
+        self.slots.len() as u64
+    }
+}
+
+/// Writing half of a broadcast ring, see [`new`].
+pub struct Writer<A> {
+    shared: Arc<Shared<A>>,
+}
+
+/// Reading half of a broadcast ring with its own cursor.
+pub struct Reader<A> {
+    shared: Arc<Shared<A>>,
+    cursor: u64,
+}
+
+/// Why [`Reader::read`] returned no element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadError {
+    /// The reader was lapped and skipped this many elements; the next
+    /// read returns the oldest element still in the ring.
+    Lagged(u64),
+    /// The reader has seen everything written so far.
+    Empty,
+}
+
+/// Create a broadcast ring keeping the last `size` elements, with one
+/// reader.  Panics if `size` is 0.
+pub fn new<A>(size: usize) -> (Writer<A>, Reader<A>) {
+    assert!(size > 0, "capacity must be greater than zero");
+    let slots = (0..size).map(|_| Mutex::new(Slot { seq: 0, val: None })).collect();
+    let shared = Arc::new(Shared {
+        slots,
+        head: CachePadded(AtomicU64::new(0)),
+        readers: AtomicUsize::new(1),
+    });
+    (Writer { shared: Arc::clone(&shared) }, Reader { shared, cursor: 0 })
+}
+
+impl<A> Writer<A> {
+    /// Publish `val` to every reader, overwriting the oldest element once
+    /// the ring is full.
+    pub fn write(&mut self, val: A) {
+        let seq = self.shared.head.load(Ordering::Relaxed);
+        let val = Arc::new(val);
+        let old = {
+            let mut slot = self.shared.lock(seq);
+            slot.seq = seq;
+            slot.val.replace(val)
+        };
+        self.shared.head.store(seq + 1, Ordering::Release);
+        drop(old);
+    }
+
+    /// Add a reader that starts with the next element written.
+    pub fn subscribe(&self) -> Reader<A> {
+        self.shared.readers.fetch_add(1, Ordering::Relaxed);
+        Reader { shared: Arc::clone(&self.shared), cursor: self.shared.head.load(Ordering::Relaxed) }
+    }
+
+    /// Number of live readers.
+    pub fn reader_count(&self) -> usize {
+        self.shared.readers.load(Ordering::Relaxed)
+    }
+
+    /// Sequence number the next write will get, i.e. the number of elements
+    /// written so far.
+    pub fn sequence(&self) -> u64 {
+        self.shared.head.load(Ordering::Relaxed)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.slots.len()
+    }
+}
+
+impl<A: Clone> Reader<A> {
+    /// Return a copy of the next element this reader has not seen yet.
+    pub fn read(&mut self) -> Result<A, ReadError> {
+        let head = self.shared.head.load(Ordering::Acquire);
+        if self.cursor == head {
+            return Err(ReadError::Empty);
+        }
+        let oldest = head.saturating_sub(self.shared.capacity());
+        if self.cursor < oldest {
+            return Err(self.skip_to(oldest));
+        }
+        let slot = self.shared.lock(self.cursor);
+        if slot.seq != self.cursor {
+            // The writer lapped us between loading `head` and locking.
+            let oldest = slot.seq + 1 - self.shared.capacity();
+            drop(slot);
+            return Err(self.skip_to(oldest));
+        }
+        let val = Arc::clone(slot.val.as_ref().expect("published slot holds a value"));
+        drop(slot);
+        self.cursor += 1;
+        Ok(Arc::unwrap_or_clone(val))
+    }
+}
+
+impl<A> Reader<A> {
+    fn skip_to(&mut self, seq: u64) -> ReadError {
+        let missed = seq - self.cursor;
+        self.cursor = seq;
+        ReadError::Lagged(missed)
+    }
+
+    /// Sequence number of the next element this reader will return.
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    /// Number of elements written that this reader has not seen, including
+    /// those already overwritten.
+    pub fn pending(&self) -> u64 {
+        self.shared.head.load(Ordering::Acquire) - self.cursor
+    }
+}
+
+impl<A> Clone for Reader<A> {
+    /// The clone starts at the same position as `self`.
+    fn clone(&self) -> Self {
+        self.shared.readers.fetch_add(1, Ordering::Relaxed);
+        Reader { shared: Arc::clone(&self.shared), cursor: self.cursor }
+    }
+}
+
+impl<A> Drop for Reader<A> {
+    fn drop(&mut self) {
+        self.shared.readers.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Lagged(n) => write!(f, "reader lagged behind and missed {} elements", n),
+            ReadError::Empty => f.write_str("no new elements to read"),
+        }
+    }
+}
+
+impl error::Error for ReadError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn every_reader_sees_every_element() {
+        let (mut writer, mut a) = new(4);
+        let mut b = writer.subscribe();
+        for i in 0..3 {
+            writer.write(i);
+        }
+        assert_eq!(a.read(), Ok(0));
+        assert_eq!(a.read(), Ok(1));
+        assert_eq!(b.read(), Ok(0));
+        assert_eq!(a.read(), Ok(2));
+        assert_eq!(a.read(), Err(ReadError::Empty));
+        assert_eq!(b.pending(), 2);
+    }
+
+    #[test]
+    fn slow_reader_is_lagged() {
+        let (mut writer, mut reader) = new(3);
+        for i in 0..10 {
+            writer.write(i);
+        }
+        assert_eq!(reader.read(), Err(ReadError::Lagged(7)));
+        assert_eq!(reader.cursor(), 7);
+        assert_eq!(reader.read(), Ok(7));
+        assert_eq!(reader.read(), Ok(8));
+        assert_eq!(reader.read(), Ok(9));
+        assert_eq!(reader.read(), Err(ReadError::Empty));
+    }
+
+    #[test]
+    fn owned_elements_are_cloned_out() {
+        let (mut writer, mut reader) = new(2);
+        let mut late = reader.clone();
+        for word in ["a", "b", "c"] {
+            writer.write(vec![word.to_string()]);
+        }
+        assert_eq!(reader.read(), Err(ReadError::Lagged(1)));
+        assert_eq!(reader.read(), Ok(vec!["b".to_string()]));
+        writer.write(vec![]);
+        assert_eq!(reader.read(), Ok(vec!["c".to_string()]));
+        assert_eq!(late.read(), Err(ReadError::Lagged(2)));
+        assert_eq!(late.read(), Ok(vec!["c".to_string()]));
+        assert_eq!(late.read(), Ok(vec![]));
+    }
+
+    #[test]
+    fn readers_come_and_go() {
+        let (mut writer, reader) = new(4);
+        writer.write("a");
+        let mut late = writer.subscribe();
+        let mut copy = reader.clone();
+        assert_eq!(writer.reader_count(), 3);
+        drop(reader);
+        assert_eq!(writer.reader_count(), 2);
+        writer.write("b");
+        assert_eq!(late.read(), Ok("b"));
+        assert_eq!(copy.read(), Ok("a"));
+        assert_eq!(copy.read(), Ok("b"));
+    }
+
+    #[test]
+    fn reads_are_never_torn() {
+        const N: u64 = 20_000;
+        let (mut writer, mut reader) = new::<[u64; 8]>(2);
+        let handle = thread::spawn(move || {
+            let mut next = 0;
+            while next < N {
+                match reader.read() {
+                    Ok(vals) => {
+                        assert_eq!(vals, [vals[0]; 8]);
+                        assert!(vals[0] >= next);
+                        next = vals[0] + 1;
+                    }
+                    Err(ReadError::Lagged(_)) => {}
+                    Err(ReadError::Empty) => thread::yield_now(),
+                }
+            }
+        });
+        for i in 0..N {
+            writer.write([i; 8]);
+            if i % 64 == 0 {
+                thread::yield_now();
+            }
+        }
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn concurrent_readers_see_increasing_sequences() {
+        const N: u64 = 20_000;
+        let (mut writer, reader) = new(16);
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let mut reader = reader.clone();
+                thread::spawn(move || {
+                    let mut seen = 0;
+                    let mut missed = 0;
+                    let mut expected = 0;
+                    while expected < N {
+                        match reader.read() {
+                            Ok(x) => {
+                                assert_eq!(x, expected);
+                                expected += 1;
+                                seen += 1;
+                            }
+                            Err(ReadError::Lagged(n)) => {
+                                expected += n;
+                                missed += n;
+                            }
+                            Err(ReadError::Empty) => thread::yield_now(),
+                        }
+                    }
+                    assert_eq!(seen + missed, N);
+                })
+            })
+            .collect();
+        for i in 0..N {
+            writer.write(i);
+            if i % 64 == 0 {
+                thread::yield_now();
+            }
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
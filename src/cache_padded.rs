@@ -0,0 +1,14 @@
+use core::ops::Deref;
+
+/// Keeps a value on its own cache line so that the indices written by
+/// different threads do not bounce the same line between cores.
+#[repr(align(64))]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
@@ -0,0 +1,537 @@
+//! Blocking bounded channel around a [`RingBuffer`].
+//!
+//! [`channel`] returns a [`Sender`] and a [`Receiver`] sharing one ring
+//! buffer behind a `Mutex`.  Sending blocks while the buffer is full and
+//! receiving blocks while it is empty; both sides notice when every handle
+//! on the other side has been dropped.  The API follows `std::sync::mpsc`.
+
+use std::error;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::RingBuffer;
+
+struct State<A> {
+    ring_buffer: RingBuffer<A>,
+    senders: usize,
+    receivers: usize,
+}
+
+struct Shared<A> {
+    state: Mutex<State<A>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<A> Shared<A> {
+    /// Ring buffer operations cannot leave the state half updated, so a
+    /// panic in another thread holding the lock does not invalidate it.
+    fn lock(&self) -> MutexGuard<'_, State<A>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Sending half of a [`channel`].
+pub struct Sender<A> {
+    shared: Arc<Shared<A>>,
+}
+
+/// Receiving half of a [`channel`].
+pub struct Receiver<A> {
+    shared: Arc<Shared<A>>,
+}
+
+/// Create a channel buffering up to `size` elements.  Panics if `size` is 0.
+pub fn channel<A>(size: usize) -> (Sender<A>, Receiver<A>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State { ring_buffer: crate::new(size), senders: 1, receivers: 1 }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (Sender { shared: Arc::clone(&shared) }, Receiver { shared })
+}
+
+/// The value could not be sent because every receiver is gone.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<A>(pub A);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<A> {
+    Full(A),
+    Disconnected(A),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SendTimeoutError<A> {
+    Timeout(A),
+    Disconnected(A),
+}
+
+/// Nothing can be received because the channel is empty and every sender
+/// is gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    Timeout,
+    Disconnected,
+}
+
+impl<A> Sender<A> {
+    /// Send `val`, waiting for room if the channel is full.
+    pub fn send(&self, val: A) -> Result<(), SendError<A>> {
+        let mut state = self.shared.lock();
+        loop {
+            if state.receivers == 0 {
+                return Err(SendError(val));
+            }
+            if !state.ring_buffer.is_full() {
+                self.store(state, val);
+                return Ok(());
+            }
+            state = self.shared.not_full.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Send `val` if there is room right away.
+    pub fn try_send(&self, val: A) -> Result<(), TrySendError<A>> {
+        let state = self.shared.lock();
+        if state.receivers == 0 {
+            Err(TrySendError::Disconnected(val))
+        } else if state.ring_buffer.is_full() {
+            Err(TrySendError::Full(val))
+        } else {
+            self.store(state, val);
+            Ok(())
+        }
+    }
+
+    /// Send `val`, waiting at most `timeout` for room.
+    ///
+    /// A timeout too long to represent waits forever, like [`Sender::send`].
+    pub fn send_timeout(&self, val: A, timeout: Duration) -> Result<(), SendTimeoutError<A>> {
+        let Some(deadline) = Instant::now().checked_add(timeout) else {
+            return self.send(val).map_err(|SendError(val)| SendTimeoutError::Disconnected(val));
+        };
+        let mut state = self.shared.lock();
+        loop {
+            if state.receivers == 0 {
+                return Err(SendTimeoutError::Disconnected(val));
+            }
+            if !state.ring_buffer.is_full() {
+                self.store(state, val);
+                return Ok(());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(SendTimeoutError::Timeout(val));
+            }
+            state = self
+                .shared
+                .not_full
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    fn store(&self, mut state: MutexGuard<'_, State<A>>, val: A) {
+        let stored = state.ring_buffer.try_push(val);
+        debug_assert!(stored.is_ok());
+        drop(state);
+        self.shared.not_empty.notify_one();
+    }
+}
+
+impl<A> Receiver<A> {
+    /// Receive the oldest element, waiting for one if the channel is empty.
+    pub fn recv(&self) -> Result<A, RecvError> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(val) = self.take(&mut state) {
+                return Ok(val);
+            }
+            if state.senders == 0 {
+                return Err(RecvError);
+            }
+            state = self.shared.not_empty.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Receive the oldest element if there is one right away.
+    pub fn try_recv(&self) -> Result<A, TryRecvError> {
+        let mut state = self.shared.lock();
+        match self.take(&mut state) {
+            Some(val) => Ok(val),
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Receive the oldest element, waiting at most `timeout` for one.
+    ///
+    /// A timeout too long to represent waits forever, like [`Receiver::recv`].
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<A, RecvTimeoutError> {
+        let Some(deadline) = Instant::now().checked_add(timeout) else {
+            return self.recv().map_err(|RecvError| RecvTimeoutError::Disconnected);
+        };
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(val) = self.take(&mut state) {
+                return Ok(val);
+            }
+            if state.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            state = self
+                .shared
+                .not_empty
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Iterate over received elements, blocking for each one, until every
+    /// sender is gone.
+    pub fn iter(&self) -> Iter<'_, A> {
+        Iter { receiver: self }
+    }
+
+    /// Iterate over the elements available right now.
+    pub fn try_iter(&self) -> TryIter<'_, A> {
+        TryIter { receiver: self }
+    }
+
+    fn take(&self, state: &mut MutexGuard<'_, State<A>>) -> Option<A> {
+        let val = state.ring_buffer.pop_front()?;
+        self.shared.not_full.notify_one();
+        Some(val)
+    }
+}
+
+impl<A> Clone for Sender<A> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Sender { shared: Arc::clone(&self.shared) }
+    }
+}
+
+impl<A> Clone for Receiver<A> {
+    fn clone(&self) -> Self {
+        self.shared.lock().receivers += 1;
+        Receiver { shared: Arc::clone(&self.shared) }
+    }
+}
+
+impl<A> Drop for Sender<A> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+impl<A> Drop for Receiver<A> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receivers -= 1;
+        if state.receivers == 0 {
+            drop(state);
+            self.shared.not_full.notify_all();
+        }
+    }
+}
+
+/// Blocking iterator over a [`Receiver`], see [`Receiver::iter`].
+pub struct Iter<'a, A> {
+    receiver: &'a Receiver<A>,
+}
+
+impl<A> Iterator for Iter<'_, A> {
+    type Item = A;
+
+    fn next(&mut self) -> Option<A> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Non-blocking iterator over a [`Receiver`], see [`Receiver::try_iter`].
+pub struct TryIter<'a, A> {
+    receiver: &'a Receiver<A>,
+}
+
+impl<A> Iterator for TryIter<'_, A> {
+    type Item = A;
+
+    fn next(&mut self) -> Option<A> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Owning blocking iterator over a [`Receiver`].
+pub struct IntoIter<A> {
+    receiver: Receiver<A>,
+}
+
+impl<A> Iterator for IntoIter<A> {
+    type Item = A;
+
+    fn next(&mut self) -> Option<A> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl<'a, A> IntoIterator for &'a Receiver<A> {
+    type Item = A;
+    type IntoIter = Iter<'a, A>;
+
+    fn into_iter(self) -> Iter<'a, A> {
+        self.iter()
+    }
+}
+
+impl<A> IntoIterator for Receiver<A> {
+    type Item = A;
+    type IntoIter = IntoIter<A>;
+
+    fn into_iter(self) -> IntoIter<A> {
+        IntoIter { receiver: self }
+    }
+}
+
+impl<A> fmt::Debug for SendError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SendError(..)")
+    }
+}
+
+impl<A> fmt::Display for SendError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("sending on a channel without receivers")
+    }
+}
+
+impl<A> error::Error for SendError<A> {}
+
+impl<A> fmt::Debug for TrySendError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("Full(..)"),
+            TrySendError::Disconnected(_) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<A> fmt::Display for TrySendError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("sending on a full channel"),
+            TrySendError::Disconnected(_) => f.write_str("sending on a channel without receivers"),
+        }
+    }
+}
+
+impl<A> error::Error for TrySendError<A> {}
+
+impl<A> fmt::Debug for SendTimeoutError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendTimeoutError::Timeout(_) => f.write_str("Timeout(..)"),
+            SendTimeoutError::Disconnected(_) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<A> fmt::Display for SendTimeoutError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendTimeoutError::Timeout(_) => f.write_str("timed out waiting on a full channel"),
+            SendTimeoutError::Disconnected(_) => {
+                f.write_str("sending on a channel without receivers")
+            }
+        }
+    }
+}
+
+impl<A> error::Error for SendTimeoutError<A> {}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("receiving on an empty channel without senders")
+    }
+}
+
+impl error::Error for RecvError {}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => f.write_str("receiving on an empty channel"),
+            TryRecvError::Disconnected => fmt::Display::fmt(&RecvError, f),
+        }
+    }
+}
+
+impl error::Error for TryRecvError {}
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvTimeoutError::Timeout => f.write_str("timed out waiting on an empty channel"),
+            RecvTimeoutError::Disconnected => fmt::Display::fmt(&RecvError, f),
+        }
+    }
+}
+
+impl error::Error for RecvTimeoutError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const SHORT: Duration = Duration::from_millis(20);
+
+    #[test]
+    fn send_and_recv_in_order() {
+        let (tx, rx) = channel(2);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn send_blocks_until_there_is_room() {
+        let (tx, rx) = channel(1);
+        tx.send(0).unwrap();
+        let sender = thread::spawn(move || {
+            for i in 1..50 {
+                tx.send(i).unwrap();
+            }
+        });
+        assert_eq!(rx.iter().collect::<Vec<_>>(), (0..50).collect::<Vec<_>>());
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn timeouts_expire() {
+        let (tx, rx) = channel(1);
+        let start = Instant::now();
+        assert_eq!(rx.recv_timeout(SHORT), Err(RecvTimeoutError::Timeout));
+        tx.send(1).unwrap();
+        assert_eq!(tx.send_timeout(2, SHORT), Err(SendTimeoutError::Timeout(2)));
+        assert!(start.elapsed() >= 2 * SHORT);
+        assert_eq!(rx.recv_timeout(SHORT), Ok(1));
+        assert_eq!(tx.send_timeout(2, SHORT), Ok(()));
+    }
+
+    #[test]
+    fn unrepresentable_timeouts_wait_forever() {
+        let (tx, rx) = channel(1);
+        let sender = thread::spawn(move || {
+            tx.send_timeout(1, Duration::MAX).unwrap();
+            tx.send_timeout(2, Duration::MAX).unwrap();
+            tx
+        });
+        assert_eq!(rx.recv_timeout(Duration::MAX), Ok(1));
+        let tx = sender.join().unwrap();
+        drop(rx);
+        assert_eq!(tx.send_timeout(3, Duration::MAX), Err(SendTimeoutError::Disconnected(3)));
+    }
+
+    #[test]
+    fn receivers_see_disconnection_after_draining() {
+        let (tx, rx) = channel(4);
+        let tx2 = tx.clone();
+        tx.send(1).unwrap();
+        drop(tx);
+        tx2.send(2).unwrap();
+        drop(tx2);
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(rx.recv(), Err(RecvError));
+        assert_eq!(rx.recv_timeout(SHORT), Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[test]
+    fn senders_see_disconnection() {
+        let (tx, rx) = channel(1);
+        tx.send(1).unwrap();
+        let blocked = {
+            let tx = tx.clone();
+            thread::spawn(move || tx.send(2))
+        };
+        thread::sleep(SHORT);
+        let rx2 = rx.clone();
+        drop(rx);
+        drop(rx2);
+        assert_eq!(blocked.join().unwrap(), Err(SendError(2)));
+        assert_eq!(tx.send(3), Err(SendError(3)));
+        assert_eq!(tx.try_send(4), Err(TrySendError::Disconnected(4)));
+        assert_eq!(tx.send_timeout(5, SHORT), Err(SendTimeoutError::Disconnected(5)));
+    }
+
+    #[test]
+    fn blocked_receiver_wakes_when_senders_drop() {
+        let (tx, rx) = channel::<i32>(1);
+        let receiver = thread::spawn(move || rx.into_iter().count());
+        thread::sleep(SHORT);
+        drop(tx);
+        assert_eq!(receiver.join().unwrap(), 0);
+    }
+
+    #[test]
+    fn pipeline_with_many_threads() {
+        let (tx, rx) = channel(4);
+        let (tx_out, rx_out) = channel(4);
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        tx.send(p * 1000 + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                let (rx, tx_out) = (rx.clone(), tx_out.clone());
+                thread::spawn(move || {
+                    for x in &rx {
+                        tx_out.send(x * 2).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop((rx, tx_out));
+        let mut results: Vec<i32> = rx_out.iter().collect();
+        results.sort_unstable();
+        assert_eq!(results, (0..4000).map(|x| x * 2).collect::<Vec<_>>());
+        for handle in producers.into_iter().chain(workers) {
+            handle.join().unwrap();
+        }
+        assert!(rx_out.try_iter().next().is_none());
+    }
+}
@@ -0,0 +1,119 @@
+use core::mem::{ManuallyDrop, MaybeUninit};
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::RingBuffer;
+
+/// Builds a full buffer whose capacity is the length of the `Vec`, or 1 if
+/// it is empty.  The `Vec`'s allocation is reused.
+impl<A> From<Vec<A>> for RingBuffer<A> {
+    fn from(vec: Vec<A>) -> Self {
+        let len = vec.len();
+        let mut vec = ManuallyDrop::new(vec);
+        // SAFETY: `MaybeUninit<A>` has the same layout as `A` and the new
+        // `Vec` takes over the allocation and its `len` elements.
+        let mut buffer = unsafe {
+            Vec::from_raw_parts(vec.as_mut_ptr() as *mut MaybeUninit<A>, len, vec.capacity())
+        };
+        buffer.resize_with(len.max(1), MaybeUninit::uninit);
+        let mut ring_buffer = RingBuffer::from_slots(buffer);
+        for idx in 0..len {
+            ring_buffer.seqs[idx] = ring_buffer.take_seq();
+        }
+        ring_buffer.set_bounds(0, len);
+        ring_buffer
+    }
+}
+
+impl<A, const N: usize> From<[A; N]> for RingBuffer<A> {
+    fn from(array: [A; N]) -> Self {
+        Self::from(Vec::from(array))
+    }
+}
+
+impl<A> From<VecDeque<A>> for RingBuffer<A> {
+    fn from(deque: VecDeque<A>) -> Self {
+        Self::from(Vec::from(deque))
+    }
+}
+
+impl<A> From<RingBuffer<A>> for Vec<A> {
+    fn from(ring_buffer: RingBuffer<A>) -> Self {
+        ring_buffer.into_vec()
+    }
+}
+
+impl<A> From<RingBuffer<A>> for VecDeque<A> {
+    fn from(ring_buffer: RingBuffer<A>) -> Self {
+        VecDeque::from(ring_buffer.into_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::wrapped;
+    use crate::{new, RingBuffer};
+    use std::collections::VecDeque;
+
+    #[test]
+    fn as_slices_splits_at_the_wrap_point() {
+        let mut rb = wrapped(4, 6);
+        assert_eq!(rb.as_slices(), (&[2, 3][..], &[4, 5][..]));
+        rb.as_mut_slices().1[0] = 40;
+        assert_eq!(rb.to_vec(), vec![2, 3, 40, 5]);
+        rb.truncate_back(2);
+        assert_eq!(rb.as_slices(), (&[2, 3][..], &[][..]));
+        assert_eq!(new::<i32>(2).as_slices(), (&[][..], &[][..]));
+    }
+
+    #[test]
+    fn make_contiguous_rotates_in_place() {
+        let mut rb = wrapped(4, 6);
+        assert_eq!(rb.make_contiguous(), &mut [2, 3, 4, 5]);
+        assert_eq!((rb.start, rb.end), (0, 4));
+        assert_eq!(rb.as_slices(), (&[2, 3, 4, 5][..], &[][..]));
+        assert_eq!(rb.push(6), Some(2));
+        assert_eq!(rb.to_vec(), vec![3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn into_vec_orders_oldest_first() {
+        assert_eq!(wrapped(4, 6).into_vec(), vec![2, 3, 4, 5]);
+        let mut rb = wrapped(4, 6);
+        rb.pop_front();
+        rb.pop_back();
+        assert_eq!(Vec::from(rb), vec![3, 4]);
+        assert_eq!(new::<i32>(3).into_vec(), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn from_std_collections() {
+        let rb = RingBuffer::from(vec![1, 2, 3]);
+        assert_eq!((rb.capacity(), rb.len()), (3, 3));
+        assert_eq!(RingBuffer::from([1, 2, 3]), rb);
+        let mut deque = VecDeque::from(vec![0, 1, 2]);
+        deque.pop_front();
+        deque.push_back(3);
+        assert_eq!(RingBuffer::from(deque), rb);
+        let mut rb = RingBuffer::from(Vec::<String>::new());
+        assert_eq!(rb.capacity(), 1);
+        rb.push(String::from("a"));
+        assert_eq!(rb.push(String::from("b")), Some(String::from("a")));
+    }
+
+    #[test]
+    fn from_vec_ignores_spare_vec_capacity() {
+        let mut vec = Vec::with_capacity(10);
+        vec.extend([1, 2]);
+        let mut rb = RingBuffer::from(vec);
+        assert_eq!(rb.capacity(), 2);
+        assert_eq!(rb.push(3), Some(1));
+    }
+
+    #[test]
+    fn into_vec_deque() {
+        let deque: VecDeque<i32> = wrapped(4, 6).into();
+        assert_eq!(deque, VecDeque::from(vec![2, 3, 4, 5]));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{RingBuffer, Storage};
+
+/// Position in a [`RingBuffer`] that stays put as elements are evicted.
+///
+/// A cursor remembers the sequence number of the next element it will
+/// return rather than a logical index, and does not borrow the buffer, so
+/// the buffer can be pushed to between reads.  When the elements it has
+/// not read yet get removed, it skips to the next remaining one and counts
+/// what it skipped in [`Cursor::missed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cursor {
+    seq: u64,
+    missed: u64,
+}
+
+impl Cursor {
+    /// A cursor whose next element is the one with sequence number `seq`.
+    pub fn new(seq: u64) -> Cursor {
+        Cursor { seq, missed: 0 }
+    }
+
+    /// Sequence number of the next element the cursor will return.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Number of elements skipped so far because they were evicted or
+    /// otherwise removed before the cursor reached them.
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    /// Return the next element and move past it, or `None` if the cursor
+    /// has caught up with the newest element.
+    pub fn next<'a, A, S: Storage<A>>(
+        &mut self,
+        ring_buffer: &'a RingBuffer<A, S>,
+    ) -> Option<&'a A> {
+        // Every number skipped here belonged to an element that was stored
+        // and then removed.
+        let idx = ring_buffer.seq_index(self.seq);
+        let Some(seq) = ring_buffer.seq_at(idx) else {
+            self.missed += ring_buffer.next_seq.saturating_sub(self.seq);
+            self.seq = self.seq.max(ring_buffer.next_seq);
+            return None;
+        };
+        self.missed += seq - self.seq;
+        self.seq = seq + 1;
+        ring_buffer.get(idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::new_array;
+
+    #[test]
+    fn cursor_follows_pushes() {
+        let mut rb = new_array::<_, 3>();
+        rb.push(1);
+        let mut cursor = rb.cursor();
+        assert_eq!(cursor.next(&rb), None);
+        rb.push(2);
+        rb.push(3);
+        assert_eq!(cursor.next(&rb), Some(&2));
+        rb.push(4);
+        rb.push(5);
+        assert_eq!(cursor.next(&rb), Some(&3));
+        assert_eq!(cursor.next(&rb), Some(&4));
+        assert_eq!(cursor.next(&rb), Some(&5));
+        assert_eq!(cursor.next(&rb), None);
+        assert_eq!(cursor.missed(), 0);
+    }
+
+    #[test]
+    fn cursor_counts_overwritten_elements() {
+        let mut rb = new_array::<_, 3>();
+        let mut cursor = rb.cursor();
+        for i in 0..8 {
+            rb.push(i);
+        }
+        assert_eq!(cursor.next(&rb), Some(&5));
+        assert_eq!((cursor.seq(), cursor.missed()), (6, 5));
+        rb.clear();
+        rb.push(8);
+        assert_eq!(cursor.next(&rb), Some(&8));
+        assert_eq!(cursor.missed(), 7);
+    }
+
+    #[test]
+    fn cursor_counts_elements_removed_from_the_back() {
+        let mut rb = new_array::<_, 4>();
+        let mut cursor = rb.cursor();
+        rb.push(100);
+        assert_eq!(cursor.next(&rb), Some(&100));
+        rb.pop_back();
+        rb.push(200);
+        assert_eq!(cursor.next(&rb), Some(&200));
+        rb.extend([1, 2, 3]);
+        rb.pop_back();
+        rb.push(4);
+        assert_eq!(cursor.next(&rb), Some(&1));
+        assert_eq!(cursor.next(&rb), Some(&2));
+        assert_eq!(cursor.next(&rb), Some(&4));
+        assert_eq!((cursor.seq(), cursor.missed()), (6, 1));
+    }
+}
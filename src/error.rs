@@ -0,0 +1,67 @@
+use core::fmt;
+
+/// Everything that can go wrong in the fallible ring buffer operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// A ring buffer needs room for at least one element.
+    ZeroCapacity,
+    /// The requested capacity does not fit in memory or exceeds the
+    /// caller's limit.
+    CapacityOverflow,
+    /// The allocator could not provide the storage.
+    AllocError,
+    /// A logical index is beyond the current length.
+    IndexOutOfRange { index: usize, len: usize },
+    /// The buffer is full and may not overwrite its oldest element.
+    Full,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ZeroCapacity => write!(f, "ring buffer capacity must be at least 1"),
+            Error::CapacityOverflow => write!(f, "ring buffer capacity overflow"),
+            Error::AllocError => write!(f, "ring buffer allocation failed"),
+            Error::IndexOutOfRange { index, len } => write!(
+                f,
+                "index {} out of bounds for ring buffer of length {}",
+                index, len
+            ),
+            Error::Full => write!(f, "ring buffer is full"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// A value refused because the ring buffer was full.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Full<A>(pub A);
+
+impl<A> Full<A> {
+    pub fn into_inner(self) -> A {
+        self.0
+    }
+}
+
+impl<A> fmt::Debug for Full<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Full(..)")
+    }
+}
+
+impl<A> fmt::Display for Full<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&Error::Full, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A> std::error::Error for Full<A> {}
+
+impl<A> From<Full<A>> for Error {
+    fn from(_: Full<A>) -> Self {
+        Error::Full
+    }
+}
@@ -0,0 +1,272 @@
+use core::iter::FusedIterator;
+#[cfg(feature = "alloc")]
+use core::mem::MaybeUninit;
+use core::ops::Range;
+use core::slice;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{RingBuffer, RingBufferView, Storage};
+
+/// Iterator over the elements removed by [`RingBuffer::drain`](crate::RingBuffer::drain).
+///
+/// Dropping it drops the elements it did not return and closes the gap
+/// they leave in the buffer.
+pub struct Drain<
+    'a,
+    A,
+    #[cfg(feature = "alloc")] S: Storage<A> = Vec<MaybeUninit<A>>,
+    #[cfg(not(feature = "alloc"))] S: Storage<A>,
+> {
+    pub(crate) ring_buffer: &'a mut RingBuffer<A, S>,
+    /// Slot of the oldest element and length when the drain started.
+    pub(crate) start: usize,
+    pub(crate) len: usize,
+    /// Logical indices being drained, and those not returned yet.
+    pub(crate) range: Range<usize>,
+    pub(crate) remaining: Range<usize>,
+}
+
+impl<A, S: Storage<A>> Drain<'_, A, S> {
+    fn physical(&self, idx: usize) -> usize {
+        (self.start + idx) % self.ring_buffer.capacity
+    }
+
+    /// Move the element at logical index `idx` out of its slot.
+    fn take(&mut self, idx: usize) -> A {
+        let slot = self.physical(idx);
+        // SAFETY: drained slots stay initialized until taken, and `idx` has
+        // just left `remaining` so it is taken only once.
+        unsafe { self.ring_buffer.buffer.slots()[slot].assume_init_read() }
+    }
+}
+
+impl<A, S: Storage<A>> Iterator for Drain<'_, A, S> {
+    type Item = A;
+
+    fn next(&mut self) -> Option<A> {
+        let idx = self.remaining.next()?;
+        Some(self.take(idx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.remaining.size_hint()
+    }
+}
+
+impl<A, S: Storage<A>> DoubleEndedIterator for Drain<'_, A, S> {
+    fn next_back(&mut self) -> Option<A> {
+        let idx = self.remaining.next_back()?;
+        Some(self.take(idx))
+    }
+}
+
+impl<A, S: Storage<A>> ExactSizeIterator for Drain<'_, A, S> {}
+
+impl<A, S: Storage<A>> FusedIterator for Drain<'_, A, S> {}
+
+impl<A, S: Storage<A>> Drop for Drain<'_, A, S> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+        let Range { start: from, end: to } = self.range;
+        let (len, count) = (self.len, to - from);
+        // Close the gap by moving whichever side of it is shorter.
+        if from < len - to {
+            for idx in (0..from).rev() {
+                let (src, dst) = (self.physical(idx), self.physical(idx + count));
+                self.ring_buffer.swap_slots(src, dst);
+            }
+            let start = (self.start + count) % self.ring_buffer.capacity;
+            self.ring_buffer.set_bounds(start, len - count);
+        } else {
+            for idx in to..len {
+                let (src, dst) = (self.physical(idx), self.physical(idx - count));
+                self.ring_buffer.swap_slots(src, dst);
+            }
+            self.ring_buffer.set_bounds(self.start, len - count);
+        }
+    }
+}
+
+/// Borrowing iterator over a ring buffer, oldest element first.
+pub struct Iter<'a, A> {
+    pub(crate) head: slice::Iter<'a, A>,
+    pub(crate) tail: slice::Iter<'a, A>,
+}
+
+impl<'a, A> Iterator for Iter<'a, A> {
+    type Item = &'a A;
+
+    fn next(&mut self) -> Option<&'a A> {
+        self.head.next().or_else(|| self.tail.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.head.len() + self.tail.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, A> DoubleEndedIterator for Iter<'a, A> {
+    fn next_back(&mut self) -> Option<&'a A> {
+        self.tail.next_back().or_else(|| self.head.next_back())
+    }
+}
+
+impl<A> ExactSizeIterator for Iter<'_, A> {}
+
+impl<A> FusedIterator for Iter<'_, A> {}
+
+impl<A> Clone for Iter<'_, A> {
+    fn clone(&self) -> Self {
+        Iter { head: self.head.clone(), tail: self.tail.clone() }
+    }
+}
+
+/// Mutably borrowing iterator over a ring buffer, oldest element first.
+pub struct IterMut<'a, A> {
+    pub(crate) head: slice::IterMut<'a, A>,
+    pub(crate) tail: slice::IterMut<'a, A>,
+}
+
+impl<'a, A> Iterator for IterMut<'a, A> {
+    type Item = &'a mut A;
+
+    fn next(&mut self) -> Option<&'a mut A> {
+        self.head.next().or_else(|| self.tail.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.head.len() + self.tail.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, A> DoubleEndedIterator for IterMut<'a, A> {
+    fn next_back(&mut self) -> Option<&'a mut A> {
+        self.tail.next_back().or_else(|| self.head.next_back())
+    }
+}
+
+impl<A> ExactSizeIterator for IterMut<'_, A> {}
+
+impl<A> FusedIterator for IterMut<'_, A> {}
+
+/// Owning iterator over a ring buffer, oldest element first.
+pub struct IntoIter<
+    A,
+    #[cfg(feature = "alloc")] S: Storage<A> = Vec<MaybeUninit<A>>,
+    #[cfg(not(feature = "alloc"))] S: Storage<A>,
+> {
+    pub(crate) ring_buffer: RingBuffer<A, S>,
+}
+
+impl<A, S: Storage<A>> Iterator for IntoIter<A, S> {
+    type Item = A;
+
+    fn next(&mut self) -> Option<A> {
+        self.ring_buffer.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.ring_buffer.len();
+        (len, Some(len))
+    }
+}
+
+impl<A, S: Storage<A>> DoubleEndedIterator for IntoIter<A, S> {
+    fn next_back(&mut self) -> Option<A> {
+        self.ring_buffer.pop_back()
+    }
+}
+
+impl<A, S: Storage<A>> ExactSizeIterator for IntoIter<A, S> {}
+
+impl<A, S: Storage<A>> FusedIterator for IntoIter<A, S> {}
+
+/// Iterator over overlapping sub-views, see
+/// [`RingBufferView::windows`](crate::RingBufferView::windows).
+pub struct Windows<'a, A> {
+    pub(crate) view: RingBufferView<'a, A>,
+    pub(crate) size: usize,
+}
+
+impl<'a, A> Iterator for Windows<'a, A> {
+    type Item = RingBufferView<'a, A>;
+
+    fn next(&mut self) -> Option<RingBufferView<'a, A>> {
+        if self.view.len() < self.size {
+            return None;
+        }
+        let window = self.view.slice(..self.size);
+        self.view = self.view.slice(1..);
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.view.len() + 1).saturating_sub(self.size);
+        (len, Some(len))
+    }
+}
+
+impl<'a, A> DoubleEndedIterator for Windows<'a, A> {
+    fn next_back(&mut self) -> Option<RingBufferView<'a, A>> {
+        let len = self.view.len();
+        if len < self.size {
+            return None;
+        }
+        let window = self.view.slice(len - self.size..);
+        self.view = self.view.slice(..len - 1);
+        Some(window)
+    }
+}
+
+impl<A> ExactSizeIterator for Windows<'_, A> {}
+
+impl<A> FusedIterator for Windows<'_, A> {}
+
+/// Iterator over non-overlapping sub-views, see
+/// [`RingBufferView::chunks`](crate::RingBufferView::chunks).
+pub struct Chunks<'a, A> {
+    pub(crate) view: RingBufferView<'a, A>,
+    pub(crate) size: usize,
+}
+
+impl<'a, A> Iterator for Chunks<'a, A> {
+    type Item = RingBufferView<'a, A>;
+
+    fn next(&mut self) -> Option<RingBufferView<'a, A>> {
+        if self.view.is_empty() {
+            return None;
+        }
+        let (chunk, rest) = self.view.split_at(self.size.min(self.view.len()));
+        self.view = rest;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.view.len().div_ceil(self.size);
+        (len, Some(len))
+    }
+}
+
+impl<'a, A> DoubleEndedIterator for Chunks<'a, A> {
+    fn next_back(&mut self) -> Option<RingBufferView<'a, A>> {
+        let len = self.view.len();
+        if len == 0 {
+            return None;
+        }
+        let last = match len % self.size {
+            0 => self.size,
+            rem => rem,
+        };
+        let (rest, chunk) = self.view.split_at(len - last);
+        self.view = rest;
+        Some(chunk)
+    }
+}
+
+impl<A> ExactSizeIterator for Chunks<'_, A> {}
+
+impl<A> FusedIterator for Chunks<'_, A> {}
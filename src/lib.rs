@@ -1,116 +1,883 @@
-use std::mem::swap;
+//! Fixed capacity ring buffers.
+//!
+//! The crate is `no_std`.  The `alloc` feature enables the heap allocated
+//! [`RingBuffer`] storage and everything built on `Vec` or `Box`, and the
+//! `std` feature (on by default) adds `std::error::Error` implementations.
+//! [`ArrayRingBuffer`] and [`FrozenRingBuffer`] work without either.
 
-struct RingBuffer<A> {
-    buffer: Vec<A>,
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod async_channel;
+#[cfg(feature = "alloc")]
+mod backoff;
+#[cfg(feature = "std")]
+pub mod broadcast;
+#[cfg(feature = "alloc")]
+mod cache_padded;
+#[cfg(feature = "std")]
+pub mod channel;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "alloc")]
+mod convert;
+mod cursor;
+mod error;
+mod iter;
+#[cfg(feature = "alloc")]
+pub mod moving_average;
+#[cfg(feature = "alloc")]
+pub mod mpmc;
+#[cfg(feature = "alloc")]
+pub mod quantile;
+#[cfg(feature = "std")]
+pub mod rate_limit;
+#[cfg(feature = "alloc")]
+pub mod seqlock;
+#[cfg(feature = "alloc")]
+mod shared;
+#[cfg(feature = "alloc")]
+mod sink;
+#[cfg(feature = "alloc")]
+pub mod spsc;
+#[cfg(feature = "alloc")]
+pub mod stats;
+mod storage;
+#[cfg(test)]
+mod test_util;
+#[cfg(feature = "std")]
+pub mod timed;
+mod traits;
+mod view;
+
+use core::marker::PhantomData;
+use core::mem::{swap, MaybeUninit};
+use core::ops::{Bound, Range, RangeBounds};
+#[cfg(feature = "alloc")]
+use core::mem::{self, ManuallyDrop};
+
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec::Vec};
+
+#[cfg(feature = "std")]
+pub use channel::channel;
+pub use cursor::Cursor;
+pub use error::{Error, Full};
+pub use iter::{Chunks, Drain, IntoIter, Iter, IterMut, Windows};
+#[cfg(feature = "alloc")]
+pub use shared::{share, SharedRingBuffer, Snapshot};
+#[cfg(feature = "alloc")]
+pub use sink::EvictionSink;
+pub use storage::Storage;
+pub use view::RingBufferView;
+
+#[cfg(feature = "alloc")]
+use sink::SinkSlot;
+use storage::sealed::Slots;
+
+/// Fixed capacity FIFO that overwrites its oldest element when full.
+///
+/// The slots live in a [`Storage`]: a heap allocated `Vec` by default, or
+/// an inline array for [`ArrayRingBuffer`].
+///
+/// `buffer` always holds `capacity` slots.  The buffer is empty when
+/// `start == 0 && end == 0`.  Otherwise `start` is the slot of the oldest
+/// element and `end` is one past the slot of the newest one, so the live
+/// slots are `start..end` when `start < end` and `start..capacity`
+/// followed by `0..end` when the contents wrap around (`start >= end`).
+///
+/// Every element stored also gets a sequence number, kept in `seqs` at the
+/// same index as its slot.  Numbers are handed out from `next_seq` and never
+/// reused, so they increase from the oldest element to the newest one but
+/// leave gaps where elements were removed from the back or the middle.
+/// Keeping one number per slot costs 8 bytes each, but removing elements
+/// from anywhere needs no bookkeeping that would allocate.
+pub struct RingBuffer<
+    A,
+    #[cfg(feature = "alloc")] S: Storage<A> = Vec<MaybeUninit<A>>,
+    #[cfg(not(feature = "alloc"))] S: Storage<A>,
+> {
+    buffer: S,
     start: usize,
     end: usize,
     capacity: usize,
+    seqs: S::Seqs,
+    /// Sequence number of the next element stored.
+    next_seq: u64,
+    policy: OverflowPolicy,
+    #[cfg(feature = "alloc")]
+    sink: SinkSlot<A>,
+    /// The elements are owned through `MaybeUninit` slots, which do not
+    /// tell the drop checker about them.
+    marker: PhantomData<A>,
 }
 
-struct RingBufferView<A> {
-    ring_buffer: RingBuffer<A>
+/// What [`RingBuffer::push`] does when the buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OverflowPolicy {
+    /// Evict the oldest element and hand it back to the caller, or to the
+    /// [`EvictionSink`] if one is registered.
+    #[default]
+    Overwrite,
+    /// Refuse the new element and hand it back to the caller.
+    Reject,
+    /// Silently drop the new element.
+    DropNewest,
+    /// Panic.
+    Panic,
+}
+
+/// Ring buffer without heap allocation, its `N` slots stored inline.
+pub type ArrayRingBuffer<A, const N: usize> = RingBuffer<A, [MaybeUninit<A>; N]>;
+
+/// Read-only ring buffer returned by [`freeze`] until it is thawed again.
+///
+/// See [`RingBuffer::view`] to inspect a buffer without giving it up.
+pub struct FrozenRingBuffer<
+    A,
+    #[cfg(feature = "alloc")] S: Storage<A> = Vec<MaybeUninit<A>>,
+    #[cfg(not(feature = "alloc"))] S: Storage<A>,
+> {
+    ring_buffer: RingBuffer<A, S>
 }
 
+#[cfg(feature = "alloc")]
 pub fn new<A>(size: usize) -> RingBuffer<A> {
     assert!(size > 0);
-    RingBuffer {
-        buffer: Vec::<A>::with_capacity(size),
-        start: 0,
-        end: 0,
-        capacity: size,
+    RingBuffer::from_slots(Vec::uninit(size))
+}
+
+/// Create an empty [`ArrayRingBuffer`]; `N` must not be 0.
+pub fn new_array<A, const N: usize>() -> ArrayRingBuffer<A, N> {
+    const { assert!(N > 0) };
+    RingBuffer::from_slots(<[MaybeUninit<A>; N]>::uninit(N))
+}
+
+/// Like [`new`] but handles a full buffer according to `policy`.
+#[cfg(feature = "alloc")]
+pub fn with_policy<A>(size: usize, policy: OverflowPolicy) -> RingBuffer<A> {
+    let mut ring_buffer = new(size);
+    ring_buffer.policy = policy;
+    ring_buffer
+}
+
+/// Like [`new`] but reports a zero size or a failed allocation instead of
+/// panicking or aborting.
+#[cfg(feature = "alloc")]
+pub fn try_new<A>(size: usize) -> Result<RingBuffer<A>, Error> {
+    if size == 0 {
+        return Err(Error::ZeroCapacity);
+    }
+    // Each slot also holds a `u64` sequence number, see `RingBuffer`.
+    let fits = |bytes: usize| size.checked_mul(bytes).is_some_and(|n| n <= isize::MAX as usize);
+    if !fits(mem::size_of::<A>()) || !fits(mem::size_of::<u64>()) {
+        return Err(Error::CapacityOverflow);
     }
+    let mut buffer = Vec::new();
+    buffer.try_reserve_exact(size).map_err(|_| Error::AllocError)?;
+    buffer.resize_with(size, MaybeUninit::uninit);
+    let seqs = <Vec<MaybeUninit<A>>>::try_seqs(size).ok_or(Error::AllocError)?;
+    Ok(RingBuffer::from_parts(buffer, seqs))
 }
 
-pub fn freeze<A>(ring_buffer: RingBuffer<A>) -> RingBufferView<A> {
-    RingBufferView { ring_buffer }
+/// Like [`try_new`] but also rejects sizes above `max_size`, for capacities
+/// read from untrusted configuration.
+#[cfg(feature = "alloc")]
+pub fn with_capacity_checked<A>(size: usize, max_size: usize) -> Result<RingBuffer<A>, Error> {
+    if size > max_size {
+        return Err(Error::CapacityOverflow);
+    }
+    try_new(size)
 }
 
-impl<A> RingBufferView<A> {
-    pub fn at<'a>(&'a self, idx: usize) -> Option<&'a A> {
-        if idx >= self.ring_buffer.capacity {
-            return None
-        }
-        let idx = (self.ring_buffer.start + idx) % self.ring_buffer.capacity;
-        if idx >= self.ring_buffer.end {
-            return None
-        }
-        return Some(&self.ring_buffer.buffer[idx])
+/// Resolve `range` into logical indices of a buffer holding `len` elements.
+fn bounds<R: RangeBounds<usize>>(range: R, len: usize) -> Result<Range<usize>, Error> {
+    let from = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let to = match range.end_bound() {
+        Bound::Included(&n) => n.saturating_add(1),
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => len,
+    };
+    if to > len {
+        return Err(Error::IndexOutOfRange { index: to, len });
+    }
+    if from > to {
+        return Err(Error::IndexOutOfRange { index: from, len });
+    }
+    Ok(from..to)
+}
+
+pub fn freeze<A, S: Storage<A>>(ring_buffer: RingBuffer<A, S>) -> FrozenRingBuffer<A, S> {
+    FrozenRingBuffer { ring_buffer }
+}
+
+impl<A, S: Storage<A>> FrozenRingBuffer<A, S> {
+    /// The element at logical index `idx`, 0 being the oldest.
+    pub fn at(&self, idx: usize) -> Option<&A> {
+        self.ring_buffer.get(idx)
+    }
+
+    /// The element with sequence number `seq`, see [`RingBuffer::get_by_seq`].
+    pub fn get_by_seq(&self, seq: u64) -> Option<&A> {
+        self.ring_buffer.get_by_seq(seq)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring_buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring_buffer.is_empty()
+    }
+
+    /// Iterate over the elements from oldest to newest.
+    pub fn iter(&self) -> Iter<'_, A> {
+        self.ring_buffer.iter()
     }
 
-    pub fn thaw(self) -> RingBuffer<A> {
+    /// Borrow the elements as a [`RingBufferView`].
+    pub fn view(&self) -> RingBufferView<'_, A> {
+        self.ring_buffer.view()
+    }
+
+    pub fn thaw(self) -> RingBuffer<A, S> {
         self.ring_buffer
     }
 }
 
-impl<A> RingBuffer<A> {
+impl<A, S: Storage<A>> IntoIterator for FrozenRingBuffer<A, S> {
+    type Item = A;
+    type IntoIter = IntoIter<A, S>;
+
+    fn into_iter(self) -> IntoIter<A, S> {
+        self.ring_buffer.into_iter()
+    }
+}
+
+impl<'a, A, S: Storage<A>> IntoIterator for &'a FrozenRingBuffer<A, S> {
+    type Item = &'a A;
+    type IntoIter = Iter<'a, A>;
+
+    fn into_iter(self) -> Iter<'a, A> {
+        self.iter()
+    }
+}
+
+impl<A, S: Storage<A>> RingBuffer<A, S> {
     pub fn len(&self) -> usize {
-        if self.start == 0 && self.end == 0 {
-            return 0;
-        } else if self.start <= self.end {
-            return self.end - self.start;
+        if self.is_empty() {
+            0
+        } else if self.start < self.end {
+            self.end - self.start
         } else {
-            return self.buffer.len() + self.end - self.start;
+            self.capacity - self.start + self.end
         }
     }
 
-    pub fn peek_first<B>(&self, cont: fn(&A) -> B) -> Option<B> {
-        if self.start == 0 && self.end == 0 {
+    pub fn is_empty(&self) -> bool {
+        self.start == 0 && self.end == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity
+    }
+
+    pub fn peek_first<B, F: FnOnce(&A) -> B>(&self, cont: F) -> Option<B> {
+        self.front().map(cont)
+    }
+
+    pub fn peek_last<B, F: FnOnce(&A) -> B>(&self, cont: F) -> Option<B> {
+        self.back().map(cont)
+    }
+
+    /// The oldest element.
+    pub fn front(&self) -> Option<&A> {
+        self.get(0)
+    }
+
+    /// The newest element.
+    pub fn back(&self) -> Option<&A> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut A> {
+        self.get_mut(0)
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut A> {
+        self.get_mut(self.len().checked_sub(1)?)
+    }
+
+    /// The element at logical index `idx`, 0 being the oldest.
+    pub fn get(&self, idx: usize) -> Option<&A> {
+        if idx >= self.len() {
             return None;
-        } else {
-            return Some(cont(&self.buffer[self.start]));
         }
+        let idx = self.physical(idx);
+        // SAFETY: logical indices below `len()` map to live slots.
+        Some(unsafe { self.buffer.slots()[idx].assume_init_ref() })
     }
 
-    pub fn peek_last<B>(&self, cont: fn(&A) -> B) -> Option<B> {
-        if self.start == 0 && self.end == 0 {
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut A> {
+        if idx >= self.len() {
             return None;
-        } else {
-            return Some(cont(&self.buffer[self.end - 1]));
         }
+        let idx = self.physical(idx);
+        // SAFETY: logical indices below `len()` map to live slots.
+        Some(unsafe { self.buffer.slots_mut()[idx].assume_init_mut() })
     }
 
+    /// Append `val` as the newest element, handling a full buffer according
+    /// to the [`OverflowPolicy`].
+    ///
+    /// Returns the element that did not end up in the buffer, if any: the
+    /// evicted oldest element under `Overwrite` or the refused `val` under
+    /// `Reject`.  See [`RingBuffer::push_checked`] to tell the two apart.
     pub fn push(&mut self, val: A) -> Option<A> {
-        if self.start == 0 {
-            if self.end >= self.capacity {
+        match self.push_checked(val) {
+            Ok(evicted) => evicted,
+            Err(Full(val)) => Some(val),
+        }
+    }
+
+    /// Like [`RingBuffer::push`] but reports a refused element as an error.
+    ///
+    /// `Ok` holds the evicted element, if any.  Only the `Reject` policy
+    /// returns `Err`.
+    pub fn push_checked(&mut self, val: A) -> Result<Option<A>, Full<A>> {
+        if !self.is_full() {
+            self.push_unchecked(val);
+            return Ok(None);
+        }
+        match self.policy {
+            OverflowPolicy::Overwrite => {
                 let mut val = val;
-                swap(&mut self.buffer[0], &mut val);
-                self.start = 1;
-                self.end = 1;
-                return Some(val);
-            } else {
-                if self.buffer.len() < self.buffer.capacity() {
-                    self.buffer.push(val);
-                } else {
-                    self.buffer[self.end] = val;
-                }
-                self.end += 1;
-                return None;
+                // SAFETY: every slot of a full buffer is initialized.
+                swap(unsafe { self.buffer.slots_mut()[self.start].assume_init_mut() }, &mut val);
+                self.seqs.as_mut()[self.start] = self.take_seq();
+                self.set_bounds((self.start + 1) % self.capacity, self.capacity);
+                Ok(self.evict(val))
             }
-        } else if self.start == self.end {
-            let mut val = val;
-            swap(&mut self.buffer[self.end], &mut val);
-            self.end += 1;
-            if self.end < self.capacity {
-                self.start = self.end;
-            } else {
-                self.start = 0;
+            OverflowPolicy::Reject => Err(Full(val)),
+            OverflowPolicy::DropNewest => Ok(None),
+            OverflowPolicy::Panic => panic!("ring buffer is full (capacity {})", self.capacity),
+        }
+    }
+
+    /// Append `val` only if there is room, whatever the policy.
+    pub fn try_push(&mut self, val: A) -> Result<(), Full<A>> {
+        if self.is_full() {
+            return Err(Full(val));
+        }
+        self.push_unchecked(val);
+        Ok(())
+    }
+
+    /// Send every element evicted by an overwrite to `sink` instead of
+    /// returning it, replacing any previously registered sink.
+    #[cfg(feature = "alloc")]
+    pub fn set_eviction_sink<E: EvictionSink<A> + Send + 'static>(&mut self, sink: E) {
+        self.sink.0 = Some(Box::new(sink));
+    }
+
+    /// Shorthand for [`RingBuffer::set_eviction_sink`] with a closure.
+    #[cfg(feature = "alloc")]
+    pub fn on_evict<F: FnMut(A) + Send + 'static>(&mut self, hook: F) {
+        self.set_eviction_sink(hook);
+    }
+
+    /// Unregister the eviction sink so evicted elements are returned again.
+    #[cfg(feature = "alloc")]
+    pub fn take_eviction_sink(&mut self) -> Option<Box<dyn EvictionSink<A> + Send>> {
+        self.sink.0.take()
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: OverflowPolicy) {
+        self.policy = policy;
+    }
+
+    /// Remove and return the oldest element.
+    pub fn pop_front(&mut self) -> Option<A> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        // SAFETY: `start` is live and the bounds move past it right away.
+        let val = unsafe { self.buffer.slots()[self.start].assume_init_read() };
+        self.set_bounds((self.start + 1) % self.capacity, len - 1);
+        Some(val)
+    }
+
+    /// Remove and return the newest element.
+    ///
+    /// Its sequence number is not given to the next element pushed.
+    pub fn pop_back(&mut self) -> Option<A> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        // SAFETY: `end - 1` is live and the bounds move before it right away.
+        let val = unsafe { self.buffer.slots()[self.end - 1].assume_init_read() };
+        self.set_bounds(self.start, len - 1);
+        Some(val)
+    }
+
+    /// Drop every element, leaving the capacity untouched.
+    ///
+    /// Sequence numbers keep counting from where they were.
+    pub fn clear(&mut self) {
+        self.truncate_front(0);
+    }
+
+    /// Shorten the buffer to `len` elements by dropping the oldest ones.
+    ///
+    /// Does nothing if the buffer already holds `len` elements or fewer.
+    pub fn truncate_front(&mut self, len: usize) {
+        while self.len() > len {
+            self.pop_front();
+        }
+    }
+
+    /// Shorten the buffer to `len` elements by dropping the newest ones.
+    ///
+    /// Does nothing if the buffer already holds `len` elements or fewer.
+    pub fn truncate_back(&mut self, len: usize) {
+        while self.len() > len {
+            self.pop_back();
+        }
+    }
+
+    /// Remove the elements in the logical `range` (0 is the oldest element)
+    /// and return them oldest first.
+    ///
+    /// The remaining elements keep their relative order and their sequence
+    /// numbers.  Panics if the range is out of bounds or decreasing, see
+    /// [`RingBuffer::try_drain`].
+    ///
+    /// The elements are removed as the iterator goes, and whatever it did
+    /// not return is dropped when it is.  Leaking it leaks the elements
+    /// from the range on.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, A, S> {
+        match self.try_drain(range) {
+            Ok(drain) => drain,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Like [`RingBuffer::drain`] but returns [`Error::IndexOutOfRange`]
+    /// instead of panicking; the buffer is left untouched in that case.
+    pub fn try_drain<R: RangeBounds<usize>>(
+        &mut self,
+        range: R,
+    ) -> Result<Drain<'_, A, S>, Error> {
+        let len = self.len();
+        let range = bounds(range, len)?;
+        let start = self.start;
+        // Keep only the elements before the range live until the drain
+        // closes the gap, so that leaking it cannot drop anything twice.
+        self.set_bounds(start, range.start);
+        Ok(Drain { ring_buffer: self, start, len, remaining: range.clone(), range })
+    }
+
+    /// Iterate over the elements from oldest to newest.
+    pub fn iter(&self) -> Iter<'_, A> {
+        let (head, tail) = self.as_slices();
+        Iter { head: head.iter(), tail: tail.iter() }
+    }
+
+    /// Sequence number of the oldest element.
+    pub fn first_seq(&self) -> Option<u64> {
+        (!self.is_empty()).then(|| self.seqs.as_ref()[self.start])
+    }
+
+    /// Sequence number of the newest element.
+    pub fn last_seq(&self) -> Option<u64> {
+        (!self.is_empty()).then(|| self.seqs.as_ref()[self.end - 1])
+    }
+
+    /// Sequence number the next pushed element will get.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// The element with sequence number `seq`, if it is still buffered.
+    ///
+    /// Unlike logical indices, sequence numbers keep referring to the same
+    /// element as others are removed, and are never given to another one.
+    pub fn get_by_seq(&self, seq: u64) -> Option<&A> {
+        let idx = self.seq_index(seq);
+        self.seq_at(idx).filter(|&found| found == seq).and_then(|_| self.get(idx))
+    }
+
+    /// Iterate from the element with sequence number `seq` to the newest,
+    /// starting with the next one still buffered if `seq` was removed.
+    pub fn since(&self, seq: u64) -> Iter<'_, A> {
+        self.slice(self.seq_index(seq)..).iter()
+    }
+
+    /// A cursor positioned at the next element pushed.
+    pub fn cursor(&self) -> Cursor {
+        Cursor::new(self.next_seq())
+    }
+
+    /// Borrow the elements as a [`RingBufferView`], oldest first.
+    pub fn view(&self) -> RingBufferView<'_, A> {
+        let (head, tail) = self.as_slices();
+        RingBufferView::new(head, tail)
+    }
+
+    /// Borrow the elements in the logical `range` (0 is the oldest element)
+    /// as a [`RingBufferView`].
+    ///
+    /// Panics if the range is out of bounds or decreasing.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> RingBufferView<'_, A> {
+        self.view().slice(range)
+    }
+
+    /// Iterate mutably over the elements from oldest to newest.
+    pub fn iter_mut(&mut self) -> IterMut<'_, A> {
+        let (head, tail) = self.as_mut_slices();
+        IterMut { head: head.iter_mut(), tail: tail.iter_mut() }
+    }
+
+    /// The elements as two slices, the first holding the oldest ones.
+    ///
+    /// The second slice is empty unless the contents wrap around the end of
+    /// the underlying storage.
+    pub fn as_slices(&self) -> (&[A], &[A]) {
+        let (head, tail) = self.live_ranges();
+        let head = &self.buffer.slots()[head];
+        let tail = &self.buffer.slots()[tail];
+        // SAFETY: `live_ranges` only covers initialized slots and
+        // `MaybeUninit<A>` has the same layout as `A`.
+        unsafe {
+            (
+                &*(head as *const [MaybeUninit<A>] as *const [A]),
+                &*(tail as *const [MaybeUninit<A>] as *const [A]),
+            )
+        }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [A], &mut [A]) {
+        let (head, tail) = self.live_ranges();
+        // `head` never starts before `tail` ends, so split there.
+        let (before, after) = self.buffer.slots_mut().split_at_mut(head.start);
+        let head = &mut after[..head.len()];
+        let tail = &mut before[tail];
+        // SAFETY: as in `as_slices`.
+        unsafe {
+            (
+                &mut *(head as *mut [MaybeUninit<A>] as *mut [A]),
+                &mut *(tail as *mut [MaybeUninit<A>] as *mut [A]),
+            )
+        }
+    }
+
+    /// Rotate the underlying storage in place so that the elements are
+    /// contiguous, oldest first, and return them as one slice.
+    pub fn make_contiguous(&mut self) -> &mut [A] {
+        let len = self.len();
+        self.buffer.slots_mut().rotate_left(self.start);
+        self.seqs.as_mut().rotate_left(self.start);
+        self.set_bounds(0, len);
+        self.as_mut_slices().0
+    }
+
+    /// Copy the elements into a `Vec` ordered oldest first.
+    #[cfg(feature = "alloc")]
+    pub fn to_vec(&self) -> Vec<A>
+    where
+        A: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+
+    /// Slot ranges of the live elements, oldest first.
+    fn live_ranges(&self) -> (Range<usize>, Range<usize>) {
+        if self.is_empty() {
+            (0..0, 0..0)
+        } else if self.start < self.end {
+            (self.start..self.end, 0..0)
+        } else {
+            (self.start..self.capacity, 0..self.end)
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn from_slots(buffer: S) -> Self {
+        let seqs = S::seqs(buffer.slots().len());
+        RingBuffer::from_parts(buffer, seqs)
+    }
+
+    fn from_parts(buffer: S, seqs: S::Seqs) -> Self {
+        RingBuffer {
+            capacity: buffer.slots().len(),
+            buffer,
+            start: 0,
+            end: 0,
+            seqs,
+            next_seq: 0,
+            policy: OverflowPolicy::default(),
+            #[cfg(feature = "alloc")]
+            sink: SinkSlot(None),
+            marker: PhantomData,
+        }
+    }
+
+    /// Hand `val` to the eviction sink, or back to the caller without one.
+    #[cfg(feature = "alloc")]
+    fn evict(&mut self, val: A) -> Option<A> {
+        match &mut self.sink.0 {
+            Some(sink) => {
+                sink.evict(val);
+                None
             }
-            return Some(val);
+            None => Some(val),
+        }
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn evict(&mut self, val: A) -> Option<A> {
+        Some(val)
+    }
+
+    /// Store `val` after the newest element; the buffer must not be full.
+    fn push_unchecked(&mut self, val: A) {
+        let len = self.len();
+        let idx = self.physical(len);
+        self.buffer.slots_mut()[idx].write(val);
+        self.seqs.as_mut()[idx] = self.take_seq();
+        self.set_bounds(self.start, len + 1);
+    }
+
+    /// Hand out the sequence number of the element being stored.
+    fn take_seq(&mut self) -> u64 {
+        self.next_seq += 1;
+        self.next_seq - 1
+    }
+
+    /// Sequence number of the element at logical index `idx`, if any.
+    fn seq_at(&self, idx: usize) -> Option<u64> {
+        (idx < self.len()).then(|| self.seqs.as_ref()[self.physical(idx)])
+    }
+
+    /// Logical index of the oldest element numbered `seq` or later, or the
+    /// length if there is none.
+    fn seq_index(&self, seq: u64) -> usize {
+        let (head, tail) = self.live_ranges();
+        let (head, tail) = (&self.seqs.as_ref()[head], &self.seqs.as_ref()[tail]);
+        let idx = head.partition_point(|&found| found < seq);
+        if idx < head.len() {
+            idx
         } else {
-            self.buffer[self.end] = val;
-            self.end += 1;
-            return None;
+            idx + tail.partition_point(|&found| found < seq)
         }
     }
+
+    /// Swap the elements and sequence numbers in slots `a` and `b`.
+    fn swap_slots(&mut self, a: usize, b: usize) {
+        self.buffer.slots_mut().swap(a, b);
+        self.seqs.as_mut().swap(a, b);
+    }
+
+    /// Map the logical index `idx` (0 is the oldest element) to its slot.
+    fn physical(&self, idx: usize) -> usize {
+        (self.start + idx) % self.capacity
+    }
+
+    /// Re-encode `start`/`end` for `len` elements starting at slot `start`.
+    fn set_bounds(&mut self, start: usize, len: usize) {
+        if len == 0 {
+            self.start = 0;
+            self.end = 0;
+        } else {
+            self.start = start;
+            self.end = (start + len - 1) % self.capacity + 1;
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<A> RingBuffer<A> {
+    /// Change the capacity to `capacity`, keeping the newest elements.
+    ///
+    /// The contents are made contiguous in a storage of exactly the new
+    /// size.  When shrinking below the current length, the oldest elements
+    /// that no longer fit are returned oldest first, or handed to the
+    /// eviction sink if one is registered.  Panics if `capacity` is 0.
+    pub fn set_capacity(&mut self, capacity: usize) -> Vec<A> {
+        assert!(capacity > 0);
+        let len = self.len();
+        let dropped: Vec<A> = self.drain(..len.saturating_sub(capacity)).collect();
+        let len = self.make_contiguous().len();
+        self.buffer.resize_with(capacity, MaybeUninit::uninit);
+        self.buffer.shrink_to_fit();
+        self.seqs.resize(capacity, 0);
+        self.seqs.shrink_to_fit();
+        self.capacity = capacity;
+        self.set_bounds(0, len);
+        if self.sink.0.is_none() {
+            return dropped;
+        }
+        for val in dropped {
+            self.evict(val);
+        }
+        Vec::new()
+    }
+
+    /// Reduce the capacity to `capacity` if it is currently larger, see
+    /// [`RingBuffer::set_capacity`].
+    pub fn shrink_to(&mut self, capacity: usize) -> Vec<A> {
+        if capacity < self.capacity {
+            self.set_capacity(capacity)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Raise the capacity to `capacity` if it is currently smaller.
+    pub fn grow_to(&mut self, capacity: usize) {
+        if capacity > self.capacity {
+            self.set_capacity(capacity);
+        }
+    }
+
+    /// Convert into a `Vec` ordered oldest first, reusing the storage.
+    pub fn into_vec(self) -> Vec<A> {
+        let (buffer, len) = self.into_slots();
+        let mut buffer = ManuallyDrop::new(buffer);
+        // SAFETY: the first `len` slots are initialized and ownership of
+        // them moves to the new `Vec`.
+        unsafe { Vec::from_raw_parts(buffer.as_mut_ptr() as *mut A, len, buffer.capacity()) }
+    }
+
+    /// Take the storage out, with the `len` elements made contiguous at its
+    /// start.  The caller becomes responsible for dropping them.
+    pub(crate) fn into_slots(mut self) -> (Vec<MaybeUninit<A>>, usize) {
+        let len = self.make_contiguous().len();
+        let buffer = mem::take(&mut self.buffer);
+        self.set_bounds(0, 0);
+        (buffer, len)
+    }
+}
+
+impl<A, S: Storage<A>> IntoIterator for RingBuffer<A, S> {
+    type Item = A;
+    type IntoIter = IntoIter<A, S>;
+
+    fn into_iter(self) -> IntoIter<A, S> {
+        IntoIter { ring_buffer: self }
+    }
+}
+
+impl<'a, A, S: Storage<A>> IntoIterator for &'a RingBuffer<A, S> {
+    type Item = &'a A;
+    type IntoIter = Iter<'a, A>;
+
+    fn into_iter(self) -> Iter<'a, A> {
+        self.iter()
+    }
+}
+
+impl<'a, A, S: Storage<A>> IntoIterator for &'a mut RingBuffer<A, S> {
+    type Item = &'a mut A;
+    type IntoIter = IterMut<'a, A>;
+
+    fn into_iter(self) -> IterMut<'a, A> {
+        self.iter_mut()
+    }
+}
+
+impl<A, S: Storage<A>> Drop for RingBuffer<A, S> {
+    fn drop(&mut self) {
+        self.clear();
+    }
 }
 
 #[cfg(test)]
+mod array_tests {
+    use super::*;
+    use crate::test_util::DropCounter;
+    use std::cell::Cell;
+
+    #[test]
+    fn array_ringbuffer_works_without_alloc() {
+        let mut arb = new_array::<i32, 3>();
+        assert_eq!(arb.push(1), None);
+        arb.extend([2, 3, 4]);
+        assert_eq!(arb.iter().copied().sum::<i32>(), 9);
+        assert_eq!((arb[0], arb.back()), (2, Some(&4)));
+        assert!(arb.view().slice(1..).iter().eq(&[3, 4]));
+        assert_eq!(arb.get_by_seq(1), Some(&2));
+        assert_eq!(arb.pop_front(), Some(2));
+        assert_eq!(arb.make_contiguous(), &mut [3, 4]);
+    }
+
+    #[test]
+    fn array_ringbuffer_drains_lazily() {
+        let drops = Cell::new(0);
+        let mut arb = new_array::<_, 4>();
+        for i in 0..6 {
+            arb.push((i, DropCounter(&drops)));
+        }
+        let mut drain = arb.drain(1..3);
+        assert_eq!(drain.next().map(|(i, _)| i), Some(3));
+        drop(drain);
+        assert_eq!(drops.get(), 4);
+        assert!(arb.iter().map(|(i, _)| *i).eq([2, 5]));
+        core::mem::forget(arb.drain(..1));
+        assert!(arb.is_empty());
+        drop(arb);
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    fn array_ringbuffer_stores_slots_inline() {
+        assert!(std::mem::size_of::<ArrayRingBuffer<u64, 64>>() >= 64 * 8);
+    }
+
+    #[test]
+    fn array_ringbuffer_drops_only_live_elements() {
+        let drops = Cell::new(0);
+        let mut arb = new_array::<_, 4>();
+        arb.push(DropCounter(&drops));
+        arb.push(DropCounter(&drops));
+        drop(arb);
+        assert_eq!(drops.get(), 2);
+        let mut arb = new_array::<_, 2>();
+        for _ in 0..5 {
+            arb.push(DropCounter(&drops));
+        }
+        assert_eq!(drops.get(), 5);
+        arb.pop_back();
+        drop(arb);
+        assert_eq!(drops.get(), 7);
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use super::*;
+    use crate::test_util::{wrapped, DropCounter};
+    use std::cell::Cell;
 
     #[test]
     fn fresh_ringbuffer_len_is_0() {
-        assert_eq!(new<&str>(5).len(), 0);
+        assert_eq!(new::<&str>(5).len(), 0);
     }
 
     fn idint(x: &i32) -> i32 {
@@ -119,13 +886,13 @@ mod tests {
 
     #[test]
     fn fresh_ringbuffer_peek_is_none() {
-        assert_eq!(new::<&str>(23).peek_first(idint), None);
-        assert_eq!(new::<bool>(3).peek_last(idint), None);
+        assert_eq!(new::<i32>(23).peek_first(idint), None);
+        assert_eq!(new::<i32>(3).peek_last(idint), None);
     }
 
     #[test]
     fn fresh_ringbuffer_peek_when_filling() {
-        let mut rb = new::<usize>(3);
+        let mut rb = new::<i32>(3);
         rb.push(3);
         assert_eq!(rb.peek_first(idint), Some(3));
         assert_eq!(rb.peek_last(idint), Some(3));
@@ -178,8 +945,391 @@ mod tests {
         assert_eq!(rbv.at(1), Some(6).as_ref());
         assert_eq!(rbv.at(2), Some(7).as_ref());
         assert_eq!(rbv.at(3), None);
-        let mut rb = rbv.thaw();
-        rb.capacity = 4;
+        let rb = rbv.thaw();
         assert_eq!(rb.capacity, 3);
     }
+
+    #[test]
+    fn ringbuffer_len_when_wrapped() {
+        let mut rb = new::<i32>(3);
+        for i in 0..7 {
+            rb.push(i);
+            assert_eq!(rb.len(), (i as usize + 1).min(3));
+        }
+        assert!(rb.is_full());
+    }
+
+    #[test]
+    fn ringbuffer_pop_front_and_back_across_wrap() {
+        let mut rb = new::<i32>(3);
+        for i in 0..5 {
+            rb.push(i);
+        }
+        assert_eq!(rb.pop_front(), Some(2));
+        assert_eq!(rb.pop_back(), Some(4));
+        assert_eq!(rb.len(), 1);
+        assert_eq!(rb.push(5), None);
+        assert_eq!(rb.push(6), None);
+        assert_eq!(rb.push(7), Some(3));
+        assert_eq!(rb.pop_back(), Some(7));
+        assert_eq!(rb.pop_front(), Some(5));
+        assert_eq!(rb.pop_front(), Some(6));
+        assert_eq!(rb.pop_front(), None);
+        assert_eq!(rb.pop_back(), None);
+        assert!(rb.is_empty());
+        assert_eq!((rb.start, rb.end), (0, 0));
+    }
+
+    #[test]
+    fn ringbuffer_truncate_and_clear() {
+        let mut rb = new::<i32>(4);
+        for i in 0..6 {
+            rb.push(i);
+        }
+        rb.truncate_front(3);
+        assert_eq!(rb.peek_first(idint), Some(3));
+        rb.truncate_back(2);
+        assert_eq!(rb.peek_last(idint), Some(4));
+        rb.truncate_back(5);
+        assert_eq!(rb.len(), 2);
+        rb.clear();
+        assert!(rb.is_empty());
+        assert_eq!(rb.push(9), None);
+        assert_eq!(rb.peek_first(idint), Some(9));
+    }
+
+    #[test]
+    fn ringbuffer_drain_middle_keeps_order() {
+        for (range, drained, rest) in [
+            (1..3, vec![3, 4], vec![2, 5, 6]),
+            (3..4, vec![5], vec![2, 3, 4, 6]),
+            (0..5, vec![2, 3, 4, 5, 6], vec![]),
+            (2..2, vec![], vec![2, 3, 4, 5, 6]),
+        ] {
+            let mut rb = new::<i32>(5);
+            for i in 0..7 {
+                rb.push(i);
+            }
+            assert_eq!(rb.drain(range).collect::<Vec<_>>(), drained);
+            let mut remaining = Vec::new();
+            while let Some(x) = rb.pop_front() {
+                remaining.push(x);
+            }
+            assert_eq!(remaining, rest);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "index 2 out of bounds")]
+    fn ringbuffer_drain_out_of_bounds_panics() {
+        let mut rb = new::<i32>(3);
+        rb.push(1);
+        rb.drain(0..2);
+    }
+
+    #[test]
+    fn ringbuffer_peek_accepts_capturing_closures() {
+        let rb = wrapped(3, 5);
+        let offset = 100;
+        assert_eq!(rb.peek_first(|x| x + offset), Some(102));
+        assert_eq!(rb.peek_last(|x| x + offset), Some(104));
+        let owned = String::from("moved");
+        assert_eq!(rb.peek_first(move |_| owned), Some(String::from("moved")));
+    }
+
+    #[test]
+    fn ringbuffer_reference_accessors() {
+        let mut rb = wrapped(3, 5);
+        assert_eq!(rb.front(), Some(&2));
+        assert_eq!(rb.back(), Some(&4));
+        assert_eq!(rb.get(1), Some(&3));
+        assert_eq!(rb.get(3), None);
+        *rb.front_mut().unwrap() = 20;
+        *rb.back_mut().unwrap() = 40;
+        *rb.get_mut(1).unwrap() = 30;
+        assert_eq!(rb.get_mut(3), None);
+        assert_eq!(rb.iter().copied().collect::<Vec<_>>(), vec![20, 30, 40]);
+        let rbv = freeze(rb);
+        assert_eq!(rbv.at(1), Some(&30));
+        let mut rb = rbv.thaw();
+        rb.clear();
+        assert_eq!(rb.front(), None);
+        assert_eq!(rb.back(), None);
+        assert_eq!(rb.front_mut(), None);
+        assert_eq!(rb.back_mut(), None);
+    }
+
+    #[test]
+    fn ringbuffer_iter_walks_oldest_to_newest_across_wrap() {
+        let rb = wrapped(4, 7);
+        assert_eq!(rb.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+        assert_eq!(rb.iter().rev().copied().collect::<Vec<_>>(), vec![6, 5, 4, 3]);
+        assert_eq!(new::<i32>(4).iter().next(), None);
+        let mut it = rb.iter();
+        assert_eq!(it.len(), 4);
+        assert_eq!(it.next(), Some(&3));
+        assert_eq!(it.next_back(), Some(&6));
+        assert_eq!(it.len(), 2);
+        assert_eq!(it.next_back(), Some(&5));
+        assert_eq!(it.next_back(), Some(&4));
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next_back(), None);
+    }
+
+    #[test]
+    fn ringbuffer_iter_mut_updates_in_place() {
+        let mut rb = wrapped(3, 5);
+        for x in rb.iter_mut() {
+            *x *= 10;
+        }
+        for x in &mut rb {
+            *x += 1;
+        }
+        assert_eq!((&rb).into_iter().copied().collect::<Vec<_>>(), vec![21, 31, 41]);
+    }
+
+    #[test]
+    fn ringbuffer_into_iter_by_value() {
+        let rb = wrapped(3, 5);
+        let mut it = rb.into_iter();
+        assert_eq!(it.len(), 3);
+        assert_eq!(it.next_back(), Some(4));
+        assert_eq!(it.collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn ringbuffer_view_iterates_like_ringbuffer() {
+        let rbv = freeze(wrapped(3, 4));
+        assert_eq!(rbv.len(), 3);
+        assert_eq!(rbv.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!((&rbv).into_iter().rev().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+        assert_eq!(rbv.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn sequence_numbers_survive_eviction() {
+        let mut rb = new(3);
+        assert_eq!((rb.first_seq(), rb.last_seq(), rb.next_seq()), (None, None, 0));
+        for i in 0..5 {
+            rb.push(i * 10);
+        }
+        assert_eq!((rb.first_seq(), rb.last_seq()), (Some(2), Some(4)));
+        assert_eq!(rb.get_by_seq(1), None);
+        assert_eq!(rb.get_by_seq(3), Some(&30));
+        assert_eq!(rb.get_by_seq(5), None);
+        assert_eq!(rb.since(3).collect::<Vec<_>>(), [&30, &40]);
+        assert_eq!(rb.since(0).count(), 3);
+        assert_eq!(rb.since(9).count(), 0);
+        rb.pop_front();
+        assert_eq!(rb.get_by_seq(3), Some(&30));
+        rb.pop_back();
+        assert_eq!(rb.get_by_seq(4), None);
+        rb.push(99);
+        assert_eq!(rb.get_by_seq(4), None);
+        assert_eq!(rb.get_by_seq(5), Some(&99));
+        assert_eq!((rb.first_seq(), rb.last_seq()), (Some(3), Some(5)));
+        assert_eq!(rb.since(4).collect::<Vec<_>>(), [&99]);
+        rb.clear();
+        assert_eq!(rb.next_seq(), 6);
+    }
+
+    #[test]
+    fn sequence_numbers_through_drain_and_clone() {
+        let mut rb = wrapped(5, 7);
+        assert_eq!(rb.first_seq(), Some(2));
+        rb.drain(..2);
+        assert_eq!(rb.get_by_seq(4), Some(&4));
+        let mut cursor = rb.cursor();
+        rb.push(7);
+        rb.push(8);
+        rb.drain(1..2);
+        assert_eq!(rb.get_by_seq(5), None);
+        assert_eq!(rb.get_by_seq(6), Some(&6));
+        rb.drain(2..3);
+        assert_eq!(cursor.next(&rb), Some(&8));
+        assert_eq!(cursor.missed(), 1);
+        let mut copy = rb.clone();
+        assert_eq!((copy.first_seq(), copy.last_seq()), (Some(4), Some(8)));
+        copy.push(9);
+        assert_eq!(copy.get_by_seq(9), Some(&9));
+        assert_eq!(freeze(copy).get_by_seq(6), Some(&6));
+        let mut rb = new(8);
+        rb.extend(0..6);
+        rb.drain(1..2);
+        assert_eq!(rb.get_by_seq(4), Some(&4));
+        rb.set_capacity(3);
+        assert_eq!(rb.get_by_seq(4), Some(&4));
+        assert_eq!(rb.get_by_seq(2), None);
+    }
+
+    #[test]
+    fn try_new_reports_bad_sizes() {
+        assert_eq!(try_new::<i32>(0).err(), Some(Error::ZeroCapacity));
+        assert_eq!(try_new::<u64>(usize::MAX / 4).err(), Some(Error::CapacityOverflow));
+        assert_eq!(try_new::<()>(usize::MAX / 4).err(), Some(Error::CapacityOverflow));
+        assert_eq!(try_new::<u8>(1 << 61).err(), Some(Error::CapacityOverflow));
+        assert_eq!(try_new::<u8>(isize::MAX as usize / 8).err(), Some(Error::AllocError));
+        let mut rb = try_new::<i32>(2).unwrap();
+        assert_eq!(rb.capacity(), 2);
+        rb.extend([1, 2, 3]);
+        assert_eq!(rb.to_vec(), vec![2, 3]);
+    }
+
+    #[test]
+    fn with_capacity_checked_enforces_limit() {
+        assert_eq!(with_capacity_checked::<i32>(11, 10).err(), Some(Error::CapacityOverflow));
+        assert_eq!(with_capacity_checked::<i32>(0, 10).err(), Some(Error::ZeroCapacity));
+        assert_eq!(with_capacity_checked::<i32>(10, 10).unwrap().capacity(), 10);
+    }
+
+    #[test]
+    fn try_drain_rejects_bad_ranges_without_changes() {
+        let mut rb = wrapped(3, 5);
+        assert_eq!(rb.try_drain(1..4).err(), Some(Error::IndexOutOfRange { index: 4, len: 3 }));
+        #[allow(clippy::reversed_empty_ranges)]
+        let err = rb.try_drain(2..1).err();
+        assert_eq!(err, Some(Error::IndexOutOfRange { index: 2, len: 3 }));
+        assert_eq!(rb.to_vec(), vec![2, 3, 4]);
+        assert_eq!(rb.try_drain(..=1).unwrap().collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(
+            Error::IndexOutOfRange { index: 4, len: 3 }.to_string(),
+            "index 4 out of bounds for ring buffer of length 3"
+        );
+    }
+
+    #[test]
+    fn overwrite_is_the_default_policy() {
+        let mut rb = new::<i32>(2);
+        assert_eq!(rb.policy(), OverflowPolicy::Overwrite);
+        rb.extend([1, 2]);
+        assert_eq!(rb.push_checked(3), Ok(Some(1)));
+        assert_eq!(rb.to_vec(), vec![2, 3]);
+    }
+
+    #[test]
+    fn reject_policy_hands_back_new_value() {
+        let mut rb = with_policy::<i32>(2, OverflowPolicy::Reject);
+        assert_eq!(rb.push(1), None);
+        assert_eq!(rb.push_checked(2), Ok(None));
+        assert_eq!(rb.push_checked(3), Err(Full(3)));
+        assert_eq!(rb.push(4), Some(4));
+        assert_eq!(rb.to_vec(), vec![1, 2]);
+        rb.pop_front();
+        assert_eq!(rb.push_checked(5), Ok(None));
+        assert_eq!(rb.to_vec(), vec![2, 5]);
+        assert_eq!(Error::from(Full(6)), Error::Full);
+    }
+
+    #[test]
+    fn drop_newest_policy_keeps_old_values() {
+        let mut rb = with_policy::<i32>(2, OverflowPolicy::DropNewest);
+        rb.extend(0..5);
+        assert_eq!(rb.push(5), None);
+        assert_eq!(rb.to_vec(), vec![0, 1]);
+        rb.set_policy(OverflowPolicy::Overwrite);
+        assert_eq!(rb.push(5), Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ring buffer is full")]
+    fn panic_policy_panics_when_full() {
+        let mut rb = with_policy::<i32>(1, OverflowPolicy::Panic);
+        rb.push(1);
+        rb.push(2);
+    }
+
+    #[test]
+    fn try_push_never_overwrites() {
+        let mut rb = new::<i32>(2);
+        assert_eq!(rb.try_push(1), Ok(()));
+        assert_eq!(rb.try_push(2), Ok(()));
+        assert_eq!(rb.try_push(3).map_err(Full::into_inner), Err(3));
+        assert_eq!(rb.to_vec(), vec![1, 2]);
+    }
+
+    #[test]
+    fn set_capacity_keeps_newest_elements() {
+        let mut rb = wrapped(4, 7);
+        assert_eq!(rb.set_capacity(2), vec![3, 4]);
+        assert_eq!((rb.capacity(), rb.to_vec()), (2, vec![5, 6]));
+        assert_eq!(rb.push(7), Some(5));
+        assert_eq!(rb.set_capacity(5), vec![]);
+        assert_eq!((rb.capacity(), rb.to_vec()), (5, vec![6, 7]));
+        rb.extend(8..12);
+        assert_eq!(rb.to_vec(), vec![7, 8, 9, 10, 11]);
+        assert_eq!(rb.buffer.len(), 5);
+    }
+
+    #[test]
+    fn shrink_and_grow_only_move_one_way() {
+        let mut rb = wrapped(3, 5);
+        assert_eq!(rb.shrink_to(4), vec![]);
+        rb.grow_to(2);
+        assert_eq!(rb.capacity(), 3);
+        rb.grow_to(4);
+        assert_eq!((rb.capacity(), rb.to_vec()), (4, vec![2, 3, 4]));
+        assert_eq!(rb.shrink_to(1), vec![2, 3]);
+        assert_eq!((rb.capacity(), rb.to_vec()), (1, vec![4]));
+        let mut empty = new::<i32>(3);
+        assert_eq!(empty.shrink_to(1), vec![]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.push(1), None);
+    }
+
+    #[test]
+    fn shrinking_feeds_the_eviction_sink() {
+        let evicted = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = std::sync::Arc::clone(&evicted);
+        let mut rb = wrapped(4, 6);
+        rb.on_evict(move |x| log.lock().unwrap().push(x));
+        assert_eq!(rb.set_capacity(1), vec![]);
+        assert_eq!(*evicted.lock().unwrap(), vec![2, 3, 4]);
+        assert_eq!(rb.to_vec(), vec![5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_capacity_rejects_zero() {
+        new::<i32>(3).set_capacity(0);
+    }
+
+    #[test]
+    fn array_ringbuffer_matches_heap_behaviour() {
+        let mut arb = new_array::<i32, 3>();
+        let mut rb = new::<i32>(3);
+        assert_eq!(arb.capacity(), 3);
+        for i in 0..7 {
+            assert_eq!(arb.push(i), rb.push(i));
+            assert_eq!(arb.peek_first(idint), rb.peek_first(idint));
+            assert_eq!(arb.peek_last(idint), rb.peek_last(idint));
+        }
+        assert_eq!(arb.pop_front(), Some(4));
+        assert_eq!(arb.iter().copied().collect::<Vec<_>>(), vec![5, 6]);
+        let arbv = freeze(arb);
+        assert_eq!(arbv.at(1), Some(&6));
+        let mut arb = arbv.thaw();
+        arb.extend([7, 8]);
+        assert_eq!(arb.make_contiguous(), &mut [6, 7, 8]);
+        assert_eq!(arb.clone(), arb);
+        assert_eq!(format!("{:?}", ArrayRingBuffer::<i32, 2>::default()), "[]");
+    }
+
+    #[test]
+    fn heap_ringbuffer_stores_slots_elsewhere() {
+        assert!(std::mem::size_of::<RingBuffer<u64>>() < 64 * 8);
+    }
+
+    #[test]
+    fn ringbuffer_drops_only_live_elements() {
+        let drops = Cell::new(0);
+        let mut rb = new(4);
+        for _ in 0..6 {
+            rb.push(DropCounter(&drops));
+        }
+        assert_eq!(drops.get(), 2);
+        rb.pop_front();
+        assert_eq!(drops.get(), 3);
+        drop(rb);
+        assert_eq!(drops.get(), 6);
+    }
 }
@@ -0,0 +1,196 @@
+//! Moving averages over a stream of samples.
+//!
+//! Every average implements [`Indicator`].  The windowed ones keep their
+//! last `period` samples in a [`RingBuffer`] and update incrementally from
+//! the new sample and the one `push` evicts.
+
+use crate::RingBuffer;
+
+/// Value computed incrementally from a stream of samples.
+pub trait Indicator {
+    /// Feed the next sample and return the new value once ready.
+    fn update(&mut self, x: f64) -> Option<f64>;
+
+    /// The current value, `None` until enough samples have been fed.
+    fn value(&self) -> Option<f64>;
+
+    /// Whether enough samples have been fed for a value.
+    fn is_ready(&self) -> bool {
+        self.value().is_some()
+    }
+}
+
+/// Unweighted mean of the last `period` samples.
+pub struct SimpleMovingAverage {
+    window: RingBuffer<f64>,
+    sum: f64,
+}
+
+/// Mean of the last `period` samples weighted `1, 2, ..., period` from
+/// oldest to newest.
+pub struct WeightedMovingAverage {
+    window: RingBuffer<f64>,
+    sum: f64,
+    /// Sum of the samples times their weights.
+    weighted_sum: f64,
+}
+
+/// Exponentially weighted mean with smoothing factor `2 / (period + 1)`,
+/// seeded with the simple mean of the first `period` samples.
+pub struct ExponentialMovingAverage {
+    period: usize,
+    alpha: f64,
+    count: usize,
+    /// Sum of the samples until `period` have been seen, the average after.
+    value: f64,
+}
+
+/// Simple moving average over `period` samples.  Panics if `period` is 0.
+pub fn simple(period: usize) -> SimpleMovingAverage {
+    SimpleMovingAverage { window: crate::new(period), sum: 0.0 }
+}
+
+/// Weighted moving average over `period` samples.  Panics if `period` is 0.
+pub fn weighted(period: usize) -> WeightedMovingAverage {
+    WeightedMovingAverage { window: crate::new(period), sum: 0.0, weighted_sum: 0.0 }
+}
+
+/// Exponential moving average over `period` samples.  Panics if `period`
+/// is 0.
+pub fn exponential(period: usize) -> ExponentialMovingAverage {
+    assert!(period > 0, "period must be greater than zero");
+    ExponentialMovingAverage { period, alpha: 2.0 / (period + 1) as f64, count: 0, value: 0.0 }
+}
+
+impl Indicator for SimpleMovingAverage {
+    fn update(&mut self, x: f64) -> Option<f64> {
+        let evicted = self.window.push(x);
+        self.sum += x - evicted.unwrap_or(0.0);
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.is_ready().then(|| self.sum / self.window.len() as f64)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.is_full()
+    }
+}
+
+impl Indicator for WeightedMovingAverage {
+    fn update(&mut self, x: f64) -> Option<f64> {
+        let len = self.window.len();
+        match self.window.push(x) {
+            // Every kept sample loses one unit of weight and the evicted
+            // one drops from 1 to 0: together that is the old sum.
+            Some(evicted) => {
+                self.weighted_sum += len as f64 * x - self.sum;
+                self.sum += x - evicted;
+            }
+            None => {
+                self.weighted_sum += (len + 1) as f64 * x;
+                self.sum += x;
+            }
+        }
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        let n = self.window.len() as f64;
+        self.is_ready().then(|| self.weighted_sum / (n * (n + 1.0) / 2.0))
+    }
+
+    fn is_ready(&self) -> bool {
+        self.window.is_full()
+    }
+}
+
+impl Indicator for ExponentialMovingAverage {
+    fn update(&mut self, x: f64) -> Option<f64> {
+        self.count = self.count.saturating_add(1);
+        if self.count < self.period {
+            self.value += x;
+        } else if self.count == self.period {
+            self.value = (self.value + x) / self.period as f64;
+        } else {
+            self.value += self.alpha * (x - self.value);
+        }
+        self.value()
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.is_ready().then_some(self.value)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.count >= self.period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    const PRICES: [f64; 10] =
+        [22.27, 22.19, 22.08, 22.17, 22.18, 22.13, 22.23, 22.43, 22.24, 22.29];
+
+    #[test]
+    fn simple_average_of_the_window() {
+        let mut sma = simple(3);
+        assert_eq!(sma.update(1.0), None);
+        assert_eq!(sma.update(2.0), None);
+        assert!(!sma.is_ready());
+        assert_eq!(sma.update(6.0), Some(3.0));
+        assert_eq!(sma.update(7.0), Some(5.0));
+        assert_eq!(sma.value(), Some(5.0));
+    }
+
+    #[test]
+    fn weighted_average_matches_direct_computation() {
+        let mut wma = weighted(4);
+        for (i, &x) in PRICES.iter().enumerate() {
+            let value = wma.update(x);
+            if i < 3 {
+                assert_eq!(value, None);
+                continue;
+            }
+            let window = &PRICES[i - 3..=i];
+            let expected = window.iter().zip(1..).map(|(x, w)| x * w as f64).sum::<f64>() / 10.0;
+            assert_close(value, expected);
+        }
+    }
+
+    #[test]
+    fn exponential_average_is_seeded_with_the_simple_one() {
+        let mut ema = exponential(3);
+        assert_eq!(ema.update(2.0), None);
+        assert_eq!(ema.update(4.0), None);
+        assert_eq!(ema.update(6.0), Some(4.0));
+        assert_eq!(ema.update(8.0), Some(6.0));
+        assert_eq!(ema.update(2.0), Some(4.0));
+        assert!(ema.is_ready());
+    }
+
+    #[test]
+    fn crossover_of_fast_and_slow_averages() {
+        /// Indices where `fast` moves to the other side of `slow`.
+        fn crossings(mut fast: impl Indicator, mut slow: impl Indicator, xs: &[f64]) -> Vec<usize> {
+            let mut above = None;
+            let mut crossings = Vec::new();
+            for (i, &x) in xs.iter().enumerate() {
+                if let (Some(f), Some(s)) = (fast.update(x), slow.update(x)) {
+                    if above.is_some_and(|above| above != (f > s)) {
+                        crossings.push(i);
+                    }
+                    above = Some(f > s);
+                }
+            }
+            crossings
+        }
+        let xs = [1.0, 1.0, 1.0, 1.0, 2.0, 3.0, 4.0, 3.0, 1.0, 0.0, 0.0, 1.0, 3.0];
+        assert_eq!(crossings(simple(2), simple(4), &xs), [4, 8, 12]);
+        assert_eq!(crossings(exponential(2), weighted(4), &xs), [4, 7, 11]);
+    }
+}
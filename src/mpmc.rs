@@ -0,0 +1,460 @@
+//! Bounded multi-producer/multi-consumer ring queue.
+//!
+//! Every slot carries a stamp telling whether it is ready to be written or
+//! read for the current lap around the ring, after Dmitry Vyukov's bounded
+//! queue.  [`Sender`] and [`Receiver`] handles are cheap to clone and can be
+//! shared by any number of threads.  A full queue is handled according to
+//! an [`OverflowPolicy`], as [`RingBuffer::push`](crate::RingBuffer::push)
+//! does.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{self, AtomicUsize, Ordering};
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::backoff::Backoff;
+use crate::cache_padded::CachePadded;
+use crate::{Full, OverflowPolicy};
+
+struct Slot<A> {
+    /// `pos` when the slot may be written for position `pos`, `pos + 1`
+    /// once it holds the value written there.
+    stamp: AtomicUsize,
+    val: UnsafeCell<MaybeUninit<A>>,
+}
+
+/// Positions are stamps combining a lap number in the high bits with a slot
+/// index in the low bits, so they can wrap around `usize` whatever the
+/// capacity.
+struct Shared<A> {
+    slots: Box<[Slot<A>]>,
+    /// Smallest power of two above the capacity: adding it to a stamp moves
+    /// to the same slot one lap later.
+    one_lap: usize,
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+    policy: OverflowPolicy,
+}
+
+// SAFETY: slot values are handed from one thread to another under the
+// stamp protocol, which only needs `A: Send`.
+unsafe impl<A: Send> Send for Shared<A> {}
+unsafe impl<A: Send> Sync for Shared<A> {}
+
+impl<A> Shared<A> {
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// The position after `pos`.
+    fn next(&self, pos: usize) -> usize {
+        let idx = pos & (self.one_lap - 1);
+        if idx + 1 < self.capacity() {
+            pos + 1
+        } else {
+            (pos & !(self.one_lap - 1)).wrapping_add(self.one_lap)
+        }
+    }
+
+    fn try_push(&self, val: A) -> Result<(), A> {
+        self.push_or_else(val, |val, tail, _, _| {
+            let head = self.head.load(Ordering::Relaxed);
+            if head.wrapping_add(self.one_lap) == tail {
+                Err(val)
+            } else {
+                Ok(val)
+            }
+        })
+    }
+
+    /// Push `val`, replacing the oldest element if the queue is full, and
+    /// return the replaced element.
+    fn force_push(&self, val: A) -> Option<A> {
+        self.push_or_else(val, |val, tail, new_tail, slot| {
+            let head = tail.wrapping_sub(self.one_lap);
+            let new_head = new_tail.wrapping_sub(self.one_lap);
+            // Moving the head past the oldest element takes it away from
+            // the receivers, and its slot is the one the tail points to.
+            if self
+                .head
+                .compare_exchange_weak(head, new_head, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                self.tail.store(new_tail, Ordering::SeqCst);
+                // SAFETY: winning the exchange reserves the full slot.
+                let old = unsafe { slot.val.get().replace(MaybeUninit::new(val)).assume_init() };
+                slot.stamp.store(tail + 1, Ordering::Release);
+                Err(old)
+            } else {
+                Ok(val)
+            }
+        })
+        .err()
+    }
+
+    /// Push `val` into the slot at the tail.  When that slot still holds
+    /// last lap's value, `full` gets `val`, the tail, the position after it
+    /// and the slot, and either returns `Ok(val)` to retry or ends the push
+    /// with `Err`.
+    fn push_or_else<F>(&self, mut val: A, full: F) -> Result<(), A>
+    where
+        F: Fn(A, usize, usize, &Slot<A>) -> Result<A, A>,
+    {
+        let mut backoff = Backoff::new();
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[tail & (self.one_lap - 1)];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+            let new_tail = self.next(tail);
+            if stamp == tail {
+                match self.tail.compare_exchange_weak(
+                    tail,
+                    new_tail,
+                    Ordering::SeqCst,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: winning the exchange reserves the slot.
+                        unsafe { (*slot.val.get()).write(val) };
+                        slot.stamp.store(tail + 1, Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => tail = current,
+                }
+            } else if stamp.wrapping_add(self.one_lap) == tail + 1 {
+                // The slot still holds last lap's value: full unless a
+                // receiver is about to take it.
+                atomic::fence(Ordering::SeqCst);
+                val = full(val, tail, new_tail, slot)?;
+                backoff.snooze();
+                tail = self.tail.load(Ordering::Relaxed);
+            } else {
+                backoff.snooze();
+                tail = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn try_pop(&self) -> Option<A> {
+        let mut backoff = Backoff::new();
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[head & (self.one_lap - 1)];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+            if stamp == head + 1 {
+                match self.head.compare_exchange_weak(
+                    head,
+                    self.next(head),
+                    Ordering::SeqCst,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: winning the exchange reserves the written slot.
+                        let val = unsafe { (*slot.val.get()).assume_init_read() };
+                        slot.stamp.store(head.wrapping_add(self.one_lap), Ordering::Release);
+                        return Some(val);
+                    }
+                    Err(current) => head = current,
+                }
+            } else if stamp == head {
+                // Not written yet: empty unless a sender is about to fill it.
+                atomic::fence(Ordering::SeqCst);
+                let tail = self.tail.load(Ordering::Relaxed);
+                if tail == head {
+                    return None;
+                }
+                backoff.snooze();
+                head = self.head.load(Ordering::Relaxed);
+            } else {
+                backoff.snooze();
+                head = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        loop {
+            let tail = self.tail.load(Ordering::SeqCst);
+            let head = self.head.load(Ordering::SeqCst);
+            if self.tail.load(Ordering::SeqCst) != tail {
+                continue;
+            }
+            let (hix, tix) = (head & (self.one_lap - 1), tail & (self.one_lap - 1));
+            return if hix < tix {
+                tix - hix
+            } else if hix > tix {
+                self.capacity() - hix + tix
+            } else if tail == head {
+                0
+            } else {
+                self.capacity()
+            };
+        }
+    }
+}
+
+impl<A> Drop for Shared<A> {
+    fn drop(&mut self) {
+        let mut head = *self.head.0.get_mut();
+        let tail = *self.tail.0.get_mut();
+        while head != tail {
+            let slot = &mut self.slots[head & (self.one_lap - 1)];
+            // SAFETY: positions between head and tail hold written values
+            // and no handle is left to race with us.
+            unsafe { slot.val.get_mut().assume_init_drop() };
+            head = self.next(head);
+        }
+    }
+}
+
+/// Sending handle of a [`mpmc`](self) queue.
+pub struct Sender<A> {
+    shared: Arc<Shared<A>>,
+}
+
+/// Receiving handle of a [`mpmc`](self) queue.
+pub struct Receiver<A> {
+    shared: Arc<Shared<A>>,
+}
+
+/// Create a queue with room for `size` elements that overwrites its oldest
+/// element when full.  Panics if `size` is 0.
+pub fn new<A>(size: usize) -> (Sender<A>, Receiver<A>) {
+    with_policy(size, OverflowPolicy::default())
+}
+
+/// Like [`new`] but handles a full queue according to `policy`.
+pub fn with_policy<A>(size: usize, policy: OverflowPolicy) -> (Sender<A>, Receiver<A>) {
+    assert!(size > 0);
+    let one_lap = (size + 1).next_power_of_two();
+    let slots: Vec<Slot<A>> = (0..size)
+        .map(|i| Slot { stamp: AtomicUsize::new(i), val: UnsafeCell::new(MaybeUninit::uninit()) })
+        .collect();
+    let shared = Arc::new(Shared {
+        slots: slots.into_boxed_slice(),
+        one_lap,
+        head: CachePadded(AtomicUsize::new(0)),
+        tail: CachePadded(AtomicUsize::new(0)),
+        policy,
+    });
+    (Sender { shared: Arc::clone(&shared) }, Receiver { shared })
+}
+
+impl<A> Sender<A> {
+    /// Append `val`, handling a full queue according to the policy, with the
+    /// same results as [`RingBuffer::push_checked`](crate::RingBuffer::push_checked).
+    ///
+    /// Under `Overwrite` the sender replaces the oldest element in place,
+    /// so each call evicts at most one element and returns it.
+    pub fn try_send(&self, val: A) -> Result<Option<A>, Full<A>> {
+        if self.shared.policy == OverflowPolicy::Overwrite {
+            return Ok(self.shared.force_push(val));
+        }
+        let val = match self.shared.try_push(val) {
+            Ok(()) => return Ok(None),
+            Err(val) => val,
+        };
+        match self.shared.policy {
+            OverflowPolicy::Overwrite => unreachable!("handled above"),
+            OverflowPolicy::Reject => Err(Full(val)),
+            OverflowPolicy::DropNewest => Ok(None),
+            OverflowPolicy::Panic => panic!("queue is full (capacity {})", self.capacity()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+}
+
+impl<A> Receiver<A> {
+    /// Remove and return the oldest element, if any.
+    pub fn try_recv(&self) -> Option<A> {
+        self.shared.try_pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+}
+
+impl<A> Clone for Sender<A> {
+    fn clone(&self) -> Self {
+        Sender { shared: Arc::clone(&self.shared) }
+    }
+}
+
+impl<A> Clone for Receiver<A> {
+    fn clone(&self) -> Self {
+        Receiver { shared: Arc::clone(&self.shared) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU64;
+    use std::thread;
+    use std::vec::Vec;
+
+    #[test]
+    fn fifo_across_laps() {
+        let (tx, rx) = with_policy(3, OverflowPolicy::Reject);
+        for round in 0..10 {
+            assert_eq!(tx.try_send(round), Ok(None));
+            assert_eq!(tx.try_send(round + 100), Ok(None));
+            assert_eq!(rx.len(), 2);
+            assert_eq!(rx.try_recv(), Some(round));
+            assert_eq!(rx.try_recv(), Some(round + 100));
+            assert_eq!(rx.try_recv(), None);
+            assert!(rx.is_empty());
+        }
+    }
+
+    #[test]
+    fn policies_mirror_ringbuffer_push_checked() {
+        for policy in [OverflowPolicy::Overwrite, OverflowPolicy::Reject, OverflowPolicy::DropNewest] {
+            let (tx, rx) = with_policy(2, policy);
+            let mut rb = crate::with_policy(2, policy);
+            for i in 0..5 {
+                assert_eq!(tx.try_send(i), rb.push_checked(i));
+                assert_eq!(tx.len(), rb.len());
+            }
+            let received: Vec<_> = core::iter::from_fn(|| rx.try_recv()).collect();
+            assert_eq!(received, rb.to_vec());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "queue is full")]
+    fn panic_policy_panics_when_full() {
+        let (tx, _rx) = with_policy(1, OverflowPolicy::Panic);
+        tx.try_send(1).unwrap();
+        let _ = tx.try_send(2);
+    }
+
+    #[test]
+    fn cloned_handles_share_the_queue() {
+        let (tx, rx) = new(4);
+        let (tx2, rx2) = (tx.clone(), rx.clone());
+        tx.try_send(1).unwrap();
+        tx2.try_send(2).unwrap();
+        assert_eq!(rx2.try_recv(), Some(1));
+        assert_eq!(rx.try_recv(), Some(2));
+        assert_eq!(tx.capacity(), 4);
+    }
+
+    #[test]
+    fn dropping_the_queue_drops_remaining_elements() {
+        let item = std::sync::Arc::new(());
+        let (tx, rx) = new(3);
+        for _ in 0..5 {
+            tx.try_send(std::sync::Arc::clone(&item)).unwrap();
+        }
+        assert_eq!(std::sync::Arc::strong_count(&item), 4);
+        drop(rx);
+        drop(tx);
+        assert_eq!(std::sync::Arc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn stress_reject_delivers_everything_once() {
+        const SENDERS: u64 = 4;
+        const ITEMS: u64 = 20_000;
+        let (tx, rx) = with_policy(8, OverflowPolicy::Reject);
+        let received = std::sync::Arc::new(AtomicU64::new(0));
+        let sum = std::sync::Arc::new(AtomicU64::new(0));
+        let senders: Vec<_> = (0..SENDERS)
+            .map(|s| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..ITEMS {
+                        let mut val = s * ITEMS + i;
+                        while let Err(Full(refused)) = tx.try_send(val) {
+                            val = refused;
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+        let receivers: Vec<_> = (0..4)
+            .map(|_| {
+                let (rx, received, sum) = (rx.clone(), received.clone(), sum.clone());
+                thread::spawn(move || {
+                    let mut last = [None; SENDERS as usize];
+                    while received.load(Ordering::Relaxed) < SENDERS * ITEMS {
+                        match rx.try_recv() {
+                            Some(val) => {
+                                let sender = (val / ITEMS) as usize;
+                                assert!(last[sender] < Some(val));
+                                last[sender] = Some(val);
+                                sum.fetch_add(val, Ordering::Relaxed);
+                                received.fetch_add(1, Ordering::Relaxed);
+                            }
+                            None => thread::yield_now(),
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in senders.into_iter().chain(receivers) {
+            handle.join().unwrap();
+        }
+        let n = SENDERS * ITEMS;
+        assert_eq!(sum.load(Ordering::Relaxed), n * (n - 1) / 2);
+        assert!(rx.is_empty());
+    }
+
+    #[test]
+    fn stress_overwrite_accounts_for_every_element() {
+        const ITEMS: u64 = 20_000;
+        let (tx, rx) = new(4);
+        let evicted = std::sync::Arc::new(AtomicU64::new(0));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let (tx, evicted) = (tx.clone(), evicted.clone());
+                thread::spawn(move || {
+                    for i in 0..ITEMS {
+                        if let Ok(Some(_)) = tx.try_send(i) {
+                            evicted.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                })
+            })
+            .collect();
+        let mut received = 0;
+        while handles.iter().any(|h| !h.is_finished()) {
+            if rx.try_recv().is_some() {
+                received += 1;
+            } else {
+                thread::yield_now();
+            }
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        received += core::iter::from_fn(|| rx.try_recv()).count() as u64;
+        assert_eq!(received + evicted.load(Ordering::Relaxed), 4 * ITEMS);
+        assert!(received >= 4);
+    }
+}
@@ -0,0 +1,166 @@
+//! Sliding-window median and percentiles.
+//!
+//! [`QuantileRing`] keeps the last `N` samples both in arrival order, in a
+//! [`RingBuffer`], and sorted, so that order statistics are read directly
+//! instead of sorting the window on every query.
+
+use core::ops::Deref;
+
+use alloc::vec::Vec;
+
+use crate::stats::Sample;
+use crate::RingBuffer;
+
+/// Window of the last `capacity` samples answering quantile queries.
+///
+/// Queries take O(1) or O(log N); each push moves O(N) elements of the
+/// sorted copy in one `memmove`.  NaN cannot be ordered and is rejected.
+///
+/// Reading the samples in arrival order goes through `Deref`.
+pub struct QuantileRing<A> {
+    samples: RingBuffer<A>,
+    sorted: Vec<A>,
+}
+
+/// Create an empty window of `size` samples.  Panics if `size` is 0.
+pub fn new<A: Sample>(size: usize) -> QuantileRing<A> {
+    QuantileRing { samples: crate::new(size), sorted: Vec::with_capacity(size) }
+}
+
+impl<A: Sample> QuantileRing<A> {
+    /// Add `val` as the newest sample, returning the oldest one if the
+    /// window was full.  Panics if `val` is NaN.
+    pub fn push(&mut self, val: A) -> Option<A> {
+        assert!(val.partial_cmp(&val).is_some(), "cannot order a NaN sample");
+        let evicted = self.samples.push(val);
+        if let Some(old) = evicted {
+            self.remove_sorted(old);
+        }
+        let idx = self.rank(val);
+        self.sorted.insert(idx, val);
+        evicted
+    }
+
+    /// Remove and return the oldest sample.
+    pub fn pop_front(&mut self) -> Option<A> {
+        let val = self.samples.pop_front()?;
+        self.remove_sorted(val);
+        Some(val)
+    }
+
+    /// Remove every sample.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.sorted.clear();
+    }
+
+    /// The samples, smallest first.
+    pub fn sorted(&self) -> &[A] {
+        &self.sorted
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+
+    /// The `q`-quantile, interpolating linearly between the two closest
+    /// samples: 0 is the minimum, 0.5 the median and 1 the maximum.
+    ///
+    /// Panics unless `0 <= q <= 1`.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        assert!((0.0..=1.0).contains(&q), "quantile {} is not between 0 and 1", q);
+        let last = self.sorted.len().checked_sub(1)?;
+        let pos = q * last as f64;
+        let below = pos as usize;
+        let low = self.sorted[below].to_f64();
+        match self.sorted.get(below + 1) {
+            Some(high) => Some(low + (pos - below as f64) * (high.to_f64() - low)),
+            None => Some(low),
+        }
+    }
+
+    /// Number of samples strictly smaller than `val`.
+    pub fn rank(&self, val: A) -> usize {
+        self.sorted.partition_point(|x| *x < val)
+    }
+
+    fn remove_sorted(&mut self, val: A) {
+        let idx = self.rank(val);
+        self.sorted.remove(idx);
+    }
+}
+
+impl<A> Deref for QuantileRing<A> {
+    type Target = RingBuffer<A>;
+
+    fn deref(&self) -> &RingBuffer<A> {
+        &self.samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::lcg;
+
+    /// Type 7 quantile of an already sorted slice, the textbook way.
+    fn expected_quantile(sorted: &[f64], q: f64) -> f64 {
+        let pos = q * (sorted.len() - 1) as f64;
+        let (below, above) = (pos.floor() as usize, pos.ceil() as usize);
+        sorted[below] + (pos - pos.floor()) * (sorted[above] - sorted[below])
+    }
+
+    #[test]
+    fn median_of_small_windows() {
+        let mut ring = new(4);
+        assert_eq!(ring.median(), None);
+        ring.push(5);
+        assert_eq!(ring.median(), Some(5.0));
+        ring.push(1);
+        ring.push(3);
+        assert_eq!(ring.median(), Some(3.0));
+        ring.push(10);
+        assert_eq!(ring.median(), Some(4.0));
+        assert_eq!(ring.push(7), Some(5));
+        assert_eq!(ring.sorted(), [1, 3, 7, 10]);
+        assert_eq!((ring.quantile(0.0), ring.quantile(1.0)), (Some(1.0), Some(10.0)));
+        assert_eq!((ring.rank(3), ring.rank(4), ring.rank(11)), (1, 2, 4));
+        assert_eq!(ring.pop_front(), Some(1));
+        assert_eq!(ring.sorted(), [3, 7, 10]);
+    }
+
+    #[test]
+    fn duplicates_are_removed_one_at_a_time() {
+        let mut ring = new(3);
+        for x in [2.0, 2.0, 1.0, 2.0, 3.0] {
+            ring.push(x);
+        }
+        assert_eq!(ring.sorted(), [1.0, 2.0, 3.0]);
+        assert_eq!(ring.rank(2.0), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN")]
+    fn nan_is_rejected() {
+        new(2).push(f64::NAN);
+    }
+
+    #[test]
+    fn matches_sorting_the_view() {
+        let mut ring = new(50);
+        for (i, x) in lcg(7).take(1000).enumerate() {
+            ring.push((x >> 44) as f64 / 16.0);
+            if i % 97 == 96 {
+                ring.pop_front();
+            }
+            let mut sorted = ring.view().to_vec();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert_eq!(ring.sorted(), sorted);
+            for q in [0.0, 0.5, 0.95, 0.99, 1.0] {
+                assert_eq!(ring.quantile(q), Some(expected_quantile(&sorted, q)));
+            }
+            let probe = sorted[sorted.len() / 3];
+            assert_eq!(ring.rank(probe), sorted.iter().filter(|&&y| y < probe).count());
+        }
+    }
+}
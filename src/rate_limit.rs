@@ -0,0 +1,332 @@
+//! Sliding-window rate limiting: at most `limit` acquisitions per
+//! `window`.
+//!
+//! Two [`Algorithm`]s are available.  The sliding log stores the time of
+//! every acquisition still in the window in a [`RingBuffer`] and is exact.
+//! The sliding window counter only counts acquisitions in the current and
+//! previous fixed windows, weighting the previous count by how much of it
+//! the sliding window still covers; it uses constant memory whatever the
+//! limit, at the cost of approximating the distribution of requests.
+//!
+//! Every method takes the current time as an argument, so a
+//! [`Clock`](crate::clock::Clock) of the caller's choice drives them.
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use crate::RingBuffer;
+
+/// How a [`RateLimiter`] remembers past acquisitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// Exact, with memory growing with the acquisitions in the window up
+    /// to the limit.
+    SlidingLog,
+    /// Approximate, with constant memory.
+    SlidingWindowCounter,
+}
+
+/// The acquisition was refused; trying again after this long will succeed
+/// if nothing else is acquired meanwhile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryAfter(pub Duration);
+
+/// Limits acquisitions to `limit` per sliding `window`.
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    state: State,
+}
+
+/// Capacity a sliding log starts with, growing up to the limit as needed.
+const INITIAL_LOG_CAPACITY: u32 = 8;
+
+enum State {
+    /// Times of the acquisitions in the window, oldest first.
+    Log(RingBuffer<Instant>),
+    Counter {
+        /// Start of the current fixed window, set by the first acquisition.
+        start: Option<Instant>,
+        current: u32,
+        previous: u32,
+    },
+}
+
+/// Create a rate limiter allowing `limit` acquisitions per `window`.
+/// Panics if `limit` or `window` is zero.
+pub fn new(algorithm: Algorithm, limit: u32, window: Duration) -> RateLimiter {
+    check(limit, window);
+    let state = match algorithm {
+        Algorithm::SlidingLog => State::Log(crate::new(limit.min(INITIAL_LOG_CAPACITY) as usize)),
+        Algorithm::SlidingWindowCounter => {
+            State::Counter { start: None, current: 0, previous: 0 }
+        }
+    };
+    RateLimiter { limit, window, state }
+}
+
+impl RateLimiter {
+    /// Record an acquisition at `now` if the limit allows it.
+    ///
+    /// Times must not go backwards from one call to the next.
+    pub fn try_acquire(&mut self, now: Instant) -> Result<(), RetryAfter> {
+        let (limit, window) = (self.limit, self.window);
+        match &mut self.state {
+            State::Log(times) => {
+                let expired = |time: &Instant| now.saturating_duration_since(*time) >= window;
+                while times.peek_first(expired) == Some(true) {
+                    times.pop_front();
+                }
+                if times.is_full() && times.capacity() < limit as usize {
+                    times.grow_to(times.capacity().saturating_mul(2).min(limit as usize));
+                }
+                if times.try_push(now).is_err() {
+                    let oldest = *times.front().expect("a full log is not empty");
+                    return Err(RetryAfter(window - now.saturating_duration_since(oldest)));
+                }
+                Ok(())
+            }
+            State::Counter { start, current, previous } => {
+                let start = start.get_or_insert(now);
+                let window_nanos = window.as_nanos();
+                let windows = now.saturating_duration_since(*start).as_nanos() / window_nanos;
+                if windows > 0 {
+                    *previous = if windows == 1 { *current } else { 0 };
+                    *current = 0;
+                    let elapsed = now.saturating_duration_since(*start).as_nanos() % window_nanos;
+                    *start = now - nanos(elapsed);
+                }
+                let (w, e) = (window_nanos, now.saturating_duration_since(*start).as_nanos());
+                let (l, c, p) = (u128::from(limit), u128::from(*current), u128::from(*previous));
+                // Allowed when `p * (w - e) / w + c + 1 <= l`, kept in
+                // integers to stay exact.
+                if p * (w - e) + (c + 1) * w <= l * w {
+                    *current += 1;
+                    return Ok(());
+                }
+                let wait = if c < l {
+                    // Wait for the previous window's weight to shrink.
+                    w - (l - 1 - c) * w / p - e
+                } else {
+                    // Wait for the next window, where the current count
+                    // becomes the one shrinking.
+                    w - e + w - (l - 1) * w / c
+                };
+                Err(RetryAfter(nanos(wait)))
+            }
+        }
+    }
+
+    /// Whether no acquisition made before `now` still counts.
+    pub fn is_idle(&self, now: Instant) -> bool {
+        match &self.state {
+            State::Log(times) => {
+                let expired = |time: &Instant| now.saturating_duration_since(*time) >= self.window;
+                times.peek_last(expired) != Some(false)
+            }
+            State::Counter { start, .. } => {
+                let idle_after = self.window.saturating_mul(2);
+                start.is_none_or(|start| now.saturating_duration_since(start) >= idle_after)
+            }
+        }
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+}
+
+fn check(limit: u32, window: Duration) {
+    assert!(limit > 0, "limit must be greater than zero");
+    assert!(!window.is_zero(), "window must be longer than zero");
+}
+
+/// `nanos` nanoseconds, or `Duration::MAX` if that is longer.
+fn nanos(nanos: u128) -> Duration {
+    match u64::try_from(nanos / 1_000_000_000) {
+        Ok(secs) => Duration::new(secs, (nanos % 1_000_000_000) as u32),
+        Err(_) => Duration::MAX,
+    }
+}
+
+/// One [`RateLimiter`] per key, created on first use.
+pub struct KeyedRateLimiter<K> {
+    algorithm: Algorithm,
+    limit: u32,
+    window: Duration,
+    limiters: HashMap<K, RateLimiter>,
+}
+
+/// Create a keyed rate limiter allowing `limit` acquisitions per `window`
+/// for each key.  Panics if `limit` or `window` is zero.
+pub fn keyed<K: Eq + Hash>(
+    algorithm: Algorithm,
+    limit: u32,
+    window: Duration,
+) -> KeyedRateLimiter<K> {
+    check(limit, window);
+    KeyedRateLimiter { algorithm, limit, window, limiters: HashMap::new() }
+}
+
+impl<K: Eq + Hash> KeyedRateLimiter<K> {
+    /// [`RateLimiter::try_acquire`] for `key`.
+    pub fn try_acquire(&mut self, key: K, now: Instant) -> Result<(), RetryAfter> {
+        let (algorithm, limit, window) = (self.algorithm, self.limit, self.window);
+        self.limiters.entry(key).or_insert_with(|| new(algorithm, limit, window)).try_acquire(now)
+    }
+
+    /// Forget the keys whose limiter is idle at `now`, see
+    /// [`RateLimiter::is_idle`].
+    pub fn prune(&mut self, now: Instant) {
+        self.limiters.retain(|_, limiter| !limiter.is_idle(now));
+    }
+
+    /// Forget `key`, resetting its limit.
+    pub fn remove(&mut self, key: &K) {
+        self.limiters.remove(key);
+    }
+
+    /// Number of keys with a limiter.
+    pub fn len(&self) -> usize {
+        self.limiters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limiters.is_empty()
+    }
+}
+
+impl fmt::Display for RetryAfter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rate limit exceeded, retry after {:?}", self.0)
+    }
+}
+
+impl error::Error for RetryAfter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use crate::test_util::lcg;
+
+    const SECOND: Duration = Duration::from_secs(1);
+    const MILLI: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn sliding_log_is_exact() {
+        let clock = ManualClock::new();
+        let mut limiter = new(Algorithm::SlidingLog, 3, 10 * SECOND);
+        for _ in 0..3 {
+            assert_eq!(limiter.try_acquire(clock.now()), Ok(()));
+            clock.advance(SECOND);
+        }
+        assert_eq!(limiter.try_acquire(clock.now()), Err(RetryAfter(7 * SECOND)));
+        clock.advance(7 * SECOND - MILLI);
+        assert_eq!(limiter.try_acquire(clock.now()), Err(RetryAfter(MILLI)));
+        clock.advance(MILLI);
+        assert_eq!(limiter.try_acquire(clock.now()), Ok(()));
+        assert_eq!(limiter.try_acquire(clock.now()), Err(RetryAfter(SECOND)));
+        assert!(!limiter.is_idle(clock.now()));
+        clock.advance(10 * SECOND);
+        assert!(limiter.is_idle(clock.now()));
+    }
+
+    #[test]
+    fn sliding_window_counter_weights_the_previous_window() {
+        let clock = ManualClock::new();
+        let mut limiter = new(Algorithm::SlidingWindowCounter, 4, 10 * SECOND);
+        clock.advance(SECOND);
+        for _ in 0..4 {
+            assert_eq!(limiter.try_acquire(clock.now()), Ok(()));
+        }
+        // The window started with the first acquisition; the fifth one has
+        // to wait until a quarter of the previous count has slid out.
+        let retry = 10 * SECOND + 10 * SECOND / 4;
+        assert_eq!(limiter.try_acquire(clock.now()), Err(RetryAfter(retry)));
+        clock.advance(retry);
+        assert_eq!(limiter.try_acquire(clock.now()), Ok(()));
+        assert_eq!(limiter.try_acquire(clock.now()), Err(RetryAfter(10 * SECOND / 4)));
+        clock.advance(25 * SECOND);
+        assert!(limiter.is_idle(clock.now()));
+        assert_eq!(limiter.try_acquire(clock.now()), Ok(()));
+    }
+
+    #[test]
+    fn retrying_after_the_hint_succeeds() {
+        for algorithm in [Algorithm::SlidingLog, Algorithm::SlidingWindowCounter] {
+            let clock = ManualClock::new();
+            let mut limiter = new(algorithm, 5, 3 * SECOND);
+            let mut log = Vec::new();
+            for x in lcg(3).take(2000) {
+                clock.advance(Duration::from_micros((x >> 33) % 900_000));
+                if let Err(RetryAfter(wait)) = limiter.try_acquire(clock.now()) {
+                    assert!(!wait.is_zero());
+                    clock.advance(wait);
+                    assert_eq!(limiter.try_acquire(clock.now()), Ok(()), "{:?}", algorithm);
+                }
+                log.push(clock.now());
+            }
+            if algorithm == Algorithm::SlidingLog {
+                for (i, &time) in log.iter().enumerate() {
+                    assert!(log[i..].iter().take_while(|&&t| t - time < 3 * SECOND).count() <= 5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sliding_log_grows_up_to_the_limit() {
+        let clock = ManualClock::new();
+        let mut limiter = new(Algorithm::SlidingLog, 100_000, SECOND);
+        let capacity = |limiter: &RateLimiter| match &limiter.state {
+            State::Log(times) => times.capacity(),
+            State::Counter { .. } => unreachable!("built as a sliding log"),
+        };
+        assert_eq!(limiter.try_acquire(clock.now()), Ok(()));
+        assert_eq!(capacity(&limiter), 8);
+        for _ in 1..100_000 {
+            assert_eq!(limiter.try_acquire(clock.now()), Ok(()));
+        }
+        assert_eq!(capacity(&limiter), 100_000);
+        assert_eq!(limiter.try_acquire(clock.now()), Err(RetryAfter(SECOND)));
+    }
+
+    #[test]
+    fn huge_windows_do_not_overflow() {
+        for algorithm in [Algorithm::SlidingLog, Algorithm::SlidingWindowCounter] {
+            let clock = ManualClock::new();
+            let mut limiter = new(algorithm, 1, Duration::MAX);
+            assert_eq!(limiter.try_acquire(clock.now()), Ok(()));
+            assert_eq!(limiter.try_acquire(clock.now()), Err(RetryAfter(Duration::MAX)));
+            clock.advance(SECOND);
+            assert!(!limiter.is_idle(clock.now()));
+        }
+    }
+
+    #[test]
+    fn keys_are_limited_independently() {
+        let clock = ManualClock::new();
+        let mut limiter = keyed(Algorithm::SlidingLog, 2, SECOND);
+        assert!(limiter.is_empty());
+        for _ in 0..2 {
+            assert_eq!(limiter.try_acquire("alice", clock.now()), Ok(()));
+        }
+        assert!(limiter.try_acquire("alice", clock.now()).is_err());
+        assert_eq!(limiter.try_acquire("bob", clock.now()), Ok(()));
+        assert_eq!(limiter.len(), 2);
+        limiter.remove(&"alice");
+        assert_eq!(limiter.try_acquire("alice", clock.now()), Ok(()));
+        clock.advance(SECOND);
+        limiter.prune(clock.now());
+        assert!(limiter.is_empty());
+        assert_eq!(RetryAfter(SECOND).to_string(), "rate limit exceeded, retry after 1s");
+    }
+}
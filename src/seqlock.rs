@@ -0,0 +1,241 @@
+//! Ring buffer of `Copy` values with optimistic, lock-free reads.
+//!
+//! A single [`SeqLockRing`] writer pushes without ever waiting, and any
+//! number of [`Reader`]s copy out the latest elements.  Readers take no
+//! lock: they copy the slots, then check against a sequence counter
+//! whether the writer overwrote any of them meanwhile, and retry if so.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::sync::atomic::{self, AtomicU64, Ordering};
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+use crate::backoff::Backoff;
+use crate::cache_padded::CachePadded;
+
+struct Shared<A> {
+    slots: Box<[UnsafeCell<MaybeUninit<A>>]>,
+    /// Twice the number of elements written so far, plus one while the
+    /// writer is storing the next one.
+    seq: CachePadded<AtomicU64>,
+}
+
+// SAFETY: slots are only written by the single writer; readers copy them
+// out and discard any copy the writer may have torn.
+unsafe impl<A: Send> Send for Shared<A> {}
+unsafe impl<A: Send> Sync for Shared<A> {}
+
+impl<A> Shared<A> {
+    fn slot(&self, seq: u64) -> *mut MaybeUninit<A> {
+        self.slots[(seq % self.slots.len() as u64) as usize].get()
+    }
+}
+
+/// Writing half of a seqlock ring, see [`new`].
+pub struct SeqLockRing<A> {
+    shared: Arc<Shared<A>>,
+    /// Number of elements written so far.
+    written: u64,
+}
+
+/// Reading handle of a [`SeqLockRing`].
+pub struct Reader<A> {
+    shared: Arc<Shared<A>>,
+}
+
+/// Create an empty seqlock ring of capacity `size`.  Panics if `size` is 0.
+pub fn new<A: Copy>(size: usize) -> SeqLockRing<A> {
+    assert!(size > 0, "capacity must be greater than zero");
+    let slots = (0..size).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect();
+    let shared = Arc::new(Shared { slots, seq: CachePadded(AtomicU64::new(0)) });
+    SeqLockRing { shared, written: 0 }
+}
+
+impl<A: Copy> SeqLockRing<A> {
+    /// Append `val`, returning the oldest element if it had to be evicted.
+    pub fn push(&mut self, val: A) -> Option<A> {
+        let slot = self.shared.slot(self.written);
+        // SAFETY: only this writer stores to the slots, and a slot is
+        // initialized once a full lap has been written.
+        let evicted = (self.written >= self.capacity() as u64)
+            .then(|| unsafe { (*slot).assume_init() });
+        self.shared.seq.store(2 * self.written + 1, Ordering::Relaxed);
+        atomic::fence(Ordering::Release);
+        // SAFETY: readers only read the slot concurrently and discard what
+        // they read if the sequence shows this write overlapped.
+        unsafe { ptr::write_volatile(slot, MaybeUninit::new(val)) };
+        self.written += 1;
+        self.shared.seq.store(2 * self.written, Ordering::Release);
+        evicted
+    }
+
+    /// A new handle reading from this ring.
+    pub fn reader(&self) -> Reader<A> {
+        Reader { shared: Arc::clone(&self.shared) }
+    }
+}
+
+impl<A> SeqLockRing<A> {
+    pub fn len(&self) -> usize {
+        self.written.min(self.capacity() as u64) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.written == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.slots.len()
+    }
+}
+
+impl<A: Copy> Reader<A> {
+    /// Copy the latest `n` elements, oldest first, to the start of `out`.
+    ///
+    /// Copies fewer if fewer were written or `out` is shorter, and returns
+    /// how many were copied.  Retries until the writer leaves the copied
+    /// slots alone for the duration of one copy.
+    pub fn read_latest(&self, n: usize, out: &mut [A]) -> usize {
+        // SAFETY: only initialized values are left in `out` when this
+        // returns, see `read_into`.
+        let out = unsafe { &mut *(out as *mut [A] as *mut [MaybeUninit<A>]) };
+        self.read_into(n, out)
+    }
+
+    /// The newest element.
+    pub fn latest(&self) -> Option<A> {
+        let mut out = [MaybeUninit::uninit()];
+        // SAFETY: a copied element is initialized.
+        (self.read_into(1, &mut out) == 1).then(|| unsafe { out[0].assume_init() })
+    }
+
+    fn read_into(&self, n: usize, out: &mut [MaybeUninit<A>]) -> usize {
+        let capacity = self.capacity() as u64;
+        let mut backoff = Backoff::new();
+        loop {
+            let before = self.shared.seq.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                backoff.snooze();
+                continue;
+            }
+            let written = before / 2;
+            let count = (n.min(out.len()) as u64).min(written).min(capacity);
+            let first = written - count;
+            for (seq, dst) in (first..written).zip(out.iter_mut()) {
+                // SAFETY: the slot holds a value unless the writer is
+                // overwriting it, which the check below detects.  The copy
+                // stays a `MaybeUninit` until then.
+                *dst = unsafe { ptr::read_volatile(self.shared.slot(seq)) };
+            }
+            atomic::fence(Ordering::Acquire);
+            let after = self.shared.seq.load(Ordering::Relaxed);
+            // Writes started since `before` store the elements numbered
+            // `written..after.div_ceil(2)`; the first one to reuse a slot
+            // copied above is numbered `first + capacity`.
+            if after.div_ceil(2) <= first + capacity {
+                return count as usize;
+            }
+            backoff.snooze();
+        }
+    }
+}
+
+impl<A> Reader<A> {
+    /// Number of elements currently in the ring.
+    pub fn len(&self) -> usize {
+        (self.shared.seq.load(Ordering::Acquire) / 2).min(self.capacity() as u64) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.slots.len()
+    }
+}
+
+impl<A> Clone for Reader<A> {
+    fn clone(&self) -> Self {
+        Reader { shared: Arc::clone(&self.shared) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn read_latest_returns_newest_oldest_first() {
+        let mut ring = new(4);
+        let reader = ring.reader();
+        let mut out = [0; 3];
+        assert_eq!(reader.read_latest(3, &mut out), 0);
+        assert_eq!(reader.latest(), None);
+        for i in 1..=2 {
+            assert_eq!(ring.push(i), None);
+        }
+        assert_eq!(reader.read_latest(3, &mut out), 2);
+        assert_eq!(out[..2], [1, 2]);
+        for i in 3..=4 {
+            ring.push(i);
+        }
+        assert_eq!(ring.push(5), Some(1));
+        assert_eq!(ring.push(6), Some(2));
+        assert_eq!(reader.read_latest(3, &mut out), 3);
+        assert_eq!(out, [4, 5, 6]);
+        assert_eq!(reader.read_latest(2, &mut out), 2);
+        assert_eq!(out[..2], [5, 6]);
+        let mut big = [0; 8];
+        assert_eq!(reader.read_latest(8, &mut big), 4);
+        assert_eq!(big[..4], [3, 4, 5, 6]);
+        assert_eq!(reader.latest(), Some(6));
+        assert_eq!((ring.len(), reader.len()), (4, 4));
+    }
+
+    #[test]
+    fn concurrent_reads_are_never_torn() {
+        #[derive(Clone, Copy)]
+        struct Sample {
+            n: u64,
+            twice: u64,
+            square: u64,
+        }
+        const N: u64 = 50_000;
+        let mut ring = new(8);
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let reader = ring.reader();
+                thread::spawn(move || {
+                    let mut out = [Sample { n: 0, twice: 0, square: 0 }; 5];
+                    let mut newest = 0;
+                    while newest + 1 < N {
+                        let count = reader.read_latest(5, &mut out);
+                        for pair in out[..count].windows(2) {
+                            assert_eq!(pair[0].n + 1, pair[1].n);
+                        }
+                        for sample in &out[..count] {
+                            assert_eq!(sample.twice, 2 * sample.n);
+                            assert_eq!(sample.square, sample.n * sample.n);
+                        }
+                        if count > 0 {
+                            assert!(out[count - 1].n >= newest);
+                            newest = out[count - 1].n;
+                        }
+                        thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+        for n in 0..N {
+            ring.push(Sample { n, twice: 2 * n, square: n * n });
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use core::fmt;
+use core::ops::Deref;
+
+use alloc::sync::Arc;
+
+use crate::sink::SinkSlot;
+use crate::RingBuffer;
+
+/// Ring buffer handing out [`Snapshot`]s of its contents.
+///
+/// The buffer lives behind an `Arc` shared with the snapshots taken since
+/// the last change.  Taking a snapshot only bumps a reference count; the
+/// first change made while a snapshot is alive clones the buffer, its
+/// elements included, and later changes work on that copy until the next
+/// snapshot.  The eviction sink moves along to the copy.
+///
+/// Reading goes through `Deref`, changing through
+/// [`SharedRingBuffer::make_mut`].
+pub struct SharedRingBuffer<A> {
+    inner: Arc<RingBuffer<A>>,
+    /// The eviction sink, taken out of `inner` while snapshots share it so
+    /// that it can be put into the copy made by the next change.
+    sink: SinkSlot<A>,
+}
+
+/// Cheaply cloneable, read-only snapshot of a [`SharedRingBuffer`].
+///
+/// Derefs to the buffer as it was when the snapshot was taken.
+pub struct Snapshot<A> {
+    inner: Arc<RingBuffer<A>>,
+}
+
+/// Wrap `ring_buffer` so that snapshots of it can be taken.
+pub fn share<A>(ring_buffer: RingBuffer<A>) -> SharedRingBuffer<A> {
+    SharedRingBuffer { inner: Arc::new(ring_buffer), sink: SinkSlot(None) }
+}
+
+impl<A: Clone> SharedRingBuffer<A> {
+    /// Mutable access to the buffer, cloning it first if a snapshot of it
+    /// is still alive.
+    pub fn make_mut(&mut self) -> &mut RingBuffer<A> {
+        let ring_buffer = Arc::make_mut(&mut self.inner);
+        if let Some(sink) = self.sink.0.take() {
+            ring_buffer.sink.0 = Some(sink);
+        }
+        ring_buffer
+    }
+
+    /// Shorthand for [`RingBuffer::push`] through [`SharedRingBuffer::make_mut`].
+    pub fn push(&mut self, val: A) -> Option<A> {
+        self.make_mut().push(val)
+    }
+
+    /// Shorthand for [`RingBuffer::pop_front`] through
+    /// [`SharedRingBuffer::make_mut`].
+    pub fn pop_front(&mut self) -> Option<A> {
+        self.make_mut().pop_front()
+    }
+
+    /// Take the buffer back, cloning it if snapshots are still alive.
+    pub fn into_inner(mut self) -> RingBuffer<A> {
+        let mut ring_buffer = Arc::unwrap_or_clone(self.inner);
+        if let Some(sink) = self.sink.0.take() {
+            ring_buffer.sink.0 = Some(sink);
+        }
+        ring_buffer
+    }
+}
+
+impl<A> SharedRingBuffer<A> {
+    /// Freeze the current contents into a [`Snapshot`] without copying them.
+    pub fn snapshot(&mut self) -> Snapshot<A> {
+        if let Some(ring_buffer) = Arc::get_mut(&mut self.inner) {
+            if let Some(sink) = ring_buffer.sink.0.take() {
+                self.sink.0 = Some(sink);
+            }
+        }
+        Snapshot { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<A> Deref for SharedRingBuffer<A> {
+    type Target = RingBuffer<A>;
+
+    fn deref(&self) -> &RingBuffer<A> {
+        &self.inner
+    }
+}
+
+impl<A> Deref for Snapshot<A> {
+    type Target = RingBuffer<A>;
+
+    fn deref(&self) -> &RingBuffer<A> {
+        &self.inner
+    }
+}
+
+impl<A> Clone for Snapshot<A> {
+    fn clone(&self) -> Self {
+        Snapshot { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<A: fmt::Debug> fmt::Debug for Snapshot<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Snapshot").field(&self.inner).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new;
+    use core::ops::Range;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    /// Counts every clone of any value sharing its counter.
+    struct Counted {
+        val: i32,
+        clones: Arc<AtomicUsize>,
+    }
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            self.clones.fetch_add(1, Ordering::Relaxed);
+            Counted { val: self.val, clones: Arc::clone(&self.clones) }
+        }
+    }
+
+    fn counted(capacity: usize, vals: Range<i32>) -> (SharedRingBuffer<Counted>, Arc<AtomicUsize>) {
+        let clones = Arc::new(AtomicUsize::new(0));
+        let mut rb = new(capacity);
+        rb.extend(vals.map(|val| Counted { val, clones: Arc::clone(&clones) }));
+        (share(rb), clones)
+    }
+
+    fn vals(rb: &RingBuffer<Counted>) -> Vec<i32> {
+        rb.iter().map(|x| x.val).collect()
+    }
+
+    #[test]
+    fn writes_without_snapshots_never_copy() {
+        let (mut rb, clones) = counted(4, 0..6);
+        for val in 6..20 {
+            rb.push(Counted { val, clones: Arc::clone(&clones) });
+        }
+        rb.pop_front();
+        assert_eq!(vals(&rb), [17, 18, 19]);
+        assert_eq!(clones.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn first_write_after_a_snapshot_copies_once() {
+        let (mut rb, clones) = counted(4, 0..6);
+        let snapshot = rb.snapshot();
+        let copies: Vec<_> = (0..10).map(|_| snapshot.clone()).collect();
+        assert_eq!(clones.load(Ordering::Relaxed), 0);
+        for val in 6..9 {
+            rb.push(Counted { val, clones: Arc::clone(&clones) });
+        }
+        assert_eq!(clones.load(Ordering::Relaxed), 4);
+        assert_eq!(vals(&snapshot), [2, 3, 4, 5]);
+        assert_eq!(vals(&copies[9]), [2, 3, 4, 5]);
+        assert_eq!(vals(&rb), [5, 6, 7, 8]);
+        assert_eq!(snapshot.first_seq(), Some(2));
+        assert_eq!(rb.first_seq(), Some(5));
+        drop((snapshot, copies));
+        rb.push(Counted { val: 9, clones: Arc::clone(&clones) });
+        assert_eq!(clones.load(Ordering::Relaxed), 4);
+        assert_eq!(vals(&rb.into_inner()), [6, 7, 8, 9]);
+        assert_eq!(clones.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn eviction_sink_survives_snapshots() {
+        let evicted = Arc::new(AtomicUsize::new(0));
+        let mut rb = new(2);
+        let count = Arc::clone(&evicted);
+        rb.on_evict(move |_| {
+            count.fetch_add(1, Ordering::Relaxed);
+        });
+        let mut rb = share(rb);
+        let mut snapshots = Vec::new();
+        for i in 0..9 {
+            assert_eq!(rb.push(i), None);
+            if i % 3 == 1 {
+                snapshots.push(rb.snapshot());
+            }
+        }
+        assert_eq!(evicted.load(Ordering::Relaxed), 7);
+        let mut rb = rb.into_inner();
+        assert_eq!(rb.push(9), None);
+        assert_eq!(evicted.load(Ordering::Relaxed), 8);
+        assert_eq!(snapshots[0].to_vec(), [0, 1]);
+    }
+
+    #[test]
+    fn readers_on_other_threads_see_consistent_snapshots() {
+        let mut rb = share(new(8));
+        rb.make_mut().extend(0..8);
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let snapshot = rb.snapshot();
+                thread::spawn(move || {
+                    let view = snapshot.view();
+                    view.windows(2).all(|w| w[0] + 1 == w[1])
+                })
+            })
+            .collect();
+        for i in 8..1000 {
+            rb.push(i);
+        }
+        for reader in readers {
+            assert!(reader.join().unwrap());
+        }
+        assert_eq!(rb.front(), Some(&992));
+    }
+}
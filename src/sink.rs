@@ -0,0 +1,88 @@
+use alloc::boxed::Box;
+
+use crate::{RingBuffer, Storage};
+
+/// Destination for the elements a [`RingBuffer`] evicts when it overwrites.
+///
+/// Closures taking the evicted element implement it, and so does
+/// `RingBuffer` itself to chain a secondary buffer behind a primary one.
+pub trait EvictionSink<A> {
+    fn evict(&mut self, val: A);
+}
+
+impl<A, F: FnMut(A)> EvictionSink<A> for F {
+    fn evict(&mut self, val: A) {
+        self(val)
+    }
+}
+
+/// Evicted elements are pushed into the secondary buffer, subject to its
+/// own overflow policy and sink.
+impl<A, S: Storage<A>> EvictionSink<A> for RingBuffer<A, S> {
+    fn evict(&mut self, val: A) {
+        self.push(val);
+    }
+}
+
+pub(crate) struct SinkSlot<A>(pub(crate) Option<Box<dyn EvictionSink<A> + Send>>);
+
+// SAFETY: the sink is only ever reached through a `&mut` to its owner, so
+// a shared reference cannot touch it from several threads.
+unsafe impl<A> Sync for SinkSlot<A> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::assert_send_sync;
+    use crate::{new, with_policy, OverflowPolicy, RingBuffer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn on_evict_receives_overwritten_elements() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let mut rb = new(2);
+        let log = Arc::clone(&evicted);
+        rb.on_evict(move |x| log.lock().unwrap().push(x));
+        assert_eq!(rb.push(1), None);
+        assert_eq!(rb.push(2), None);
+        assert_eq!(rb.push(3), None);
+        assert_eq!(rb.push_checked(4), Ok(None));
+        rb.extend([5, 6]);
+        assert_eq!(*evicted.lock().unwrap(), vec![1, 2, 3, 4]);
+        assert!(rb.take_eviction_sink().is_some());
+        assert_eq!(rb.push(7), Some(5));
+    }
+
+    #[test]
+    fn sink_ignores_non_overwriting_policies() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut rb = with_policy(1, OverflowPolicy::Reject);
+        let counter = Arc::clone(&count);
+        rb.on_evict(move |_: i32| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        rb.push(1);
+        assert_eq!(rb.push(2), Some(2));
+        rb.pop_front();
+        rb.clear();
+        assert_eq!(count.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn chained_sinks_forward_oldest_elements() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut archive = new(2);
+        let log = Arc::clone(&seen);
+        archive.on_evict(move |x| log.lock().unwrap().push(x));
+        let mut rb: RingBuffer<i32> = new(2);
+        rb.set_eviction_sink(archive);
+        rb.extend(1..=6);
+        assert_eq!(*seen.lock().unwrap(), vec![1, 2]);
+        assert_eq!(rb.clone().take_eviction_sink().map(|_| ()), None);
+    }
+
+    #[test]
+    fn ringbuffer_with_sink_stays_send_and_sync() {
+        assert_send_sync::<RingBuffer<i32>>();
+    }
+}
@@ -0,0 +1,380 @@
+//! Lock-free single-producer/single-consumer ring buffer.
+//!
+//! [`split`] hands the contents and storage of a [`RingBuffer`] to a
+//! [`Producer`] and a [`Consumer`] that can live on different threads.
+//! Each side owns one index and only reads the other's, so no locks are
+//! involved.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+use crate::cache_padded::CachePadded;
+use crate::{Full, RingBuffer};
+
+/// Both indices count positions modulo `2 * capacity` so that a full ring
+/// (`tail - head == capacity`) can be told apart from an empty one
+/// (`tail == head`), the same distinction `RingBuffer` makes with its
+/// `start`/`end` encoding.
+struct Shared<A> {
+    slots: Box<[UnsafeCell<MaybeUninit<A>>]>,
+    /// Next position to read, only written by the consumer.
+    head: CachePadded<AtomicUsize>,
+    /// Next position to write, only written by the producer.
+    tail: CachePadded<AtomicUsize>,
+}
+
+impl<A> Shared<A> {
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn len(&self, head: usize, tail: usize) -> usize {
+        if tail >= head {
+            tail - head
+        } else {
+            2 * self.capacity() + tail - head
+        }
+    }
+
+    fn slot(&self, pos: usize) -> *mut MaybeUninit<A> {
+        let idx = if pos >= self.capacity() { pos - self.capacity() } else { pos };
+        self.slots[idx].get()
+    }
+
+    /// Move the position `pos` forward by `n`.
+    fn advance(&self, pos: usize, n: usize) -> usize {
+        let pos = pos + n;
+        if pos >= 2 * self.capacity() {
+            pos - 2 * self.capacity()
+        } else {
+            pos
+        }
+    }
+
+    /// The positions from `pos` onwards as up to two runs of contiguous slots.
+    fn runs(&self, pos: usize, n: usize) -> [(usize, usize); 2] {
+        let idx = if pos >= self.capacity() { pos - self.capacity() } else { pos };
+        let first = n.min(self.capacity() - idx);
+        [(idx, first), (0, n - first)]
+    }
+}
+
+impl<A> Drop for Shared<A> {
+    fn drop(&mut self) {
+        let mut head = *self.head.0.get_mut();
+        let tail = *self.tail.0.get_mut();
+        while head != tail {
+            // SAFETY: positions between head and tail hold live elements and
+            // nobody else can reach them any more.
+            unsafe { (*self.slot(head)).assume_init_drop() };
+            head = self.advance(head, 1);
+        }
+    }
+}
+
+/// Writing half of a single-producer/single-consumer ring.
+pub struct Producer<A> {
+    shared: Arc<Shared<A>>,
+}
+
+/// Reading half of a single-producer/single-consumer ring.
+pub struct Consumer<A> {
+    shared: Arc<Shared<A>>,
+}
+
+// SAFETY: each half only touches the slots its index grants it, and hands
+// elements over to the other thread, hence the `A: Send` bound.  Shared
+// references only reach the atomics.
+unsafe impl<A: Send> Send for Producer<A> {}
+unsafe impl<A: Send> Sync for Producer<A> {}
+unsafe impl<A: Send> Send for Consumer<A> {}
+unsafe impl<A: Send> Sync for Consumer<A> {}
+
+/// Create an empty ring with room for `size` elements.  Panics if `size`
+/// is 0.
+pub fn new<A>(size: usize) -> (Producer<A>, Consumer<A>) {
+    split(crate::new(size))
+}
+
+/// Turn `ring_buffer` into a producer and consumer pair that keeps its
+/// capacity, its storage and its elements, oldest first.
+pub fn split<A>(ring_buffer: RingBuffer<A>) -> (Producer<A>, Consumer<A>) {
+    assert!(ring_buffer.capacity() <= usize::MAX / 2);
+    let (slots, len) = ring_buffer.into_slots();
+    let slots = Box::into_raw(slots.into_boxed_slice());
+    // SAFETY: `UnsafeCell` is `repr(transparent)` so the slice layouts match.
+    let slots = unsafe { Box::from_raw(slots as *mut [UnsafeCell<MaybeUninit<A>>]) };
+    let shared = Arc::new(Shared {
+        slots,
+        head: CachePadded(AtomicUsize::new(0)),
+        tail: CachePadded(AtomicUsize::new(len)),
+    });
+    (Producer { shared: Arc::clone(&shared) }, Consumer { shared })
+}
+
+impl<A> Producer<A> {
+    /// Append `val` unless the ring is full.
+    pub fn try_push(&mut self, val: A) -> Result<(), Full<A>> {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+        if shared.len(head, tail) == shared.capacity() {
+            return Err(Full(val));
+        }
+        // SAFETY: the slot at `tail` is free and the consumer will not read
+        // it before the release store below.
+        unsafe { (*shared.slot(tail)).write(val) };
+        shared.tail.store(shared.advance(tail, 1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Append as many elements of `vals` as fit and return how many.
+    pub fn push_slice(&mut self, vals: &[A]) -> usize
+    where
+        A: Copy,
+    {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+        let n = vals.len().min(shared.capacity() - shared.len(head, tail));
+        let mut copied = 0;
+        for (idx, count) in shared.runs(tail, n) {
+            // SAFETY: the `n` slots from `tail` are free, as in `try_push`.
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    vals[copied..].as_ptr(),
+                    shared.slots[idx].get() as *mut A,
+                    count,
+                )
+            };
+            copied += count;
+        }
+        shared.tail.store(shared.advance(tail, n), Ordering::Release);
+        n
+    }
+
+    pub fn len(&self) -> usize {
+        len(&self.shared)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+}
+
+impl<A> Consumer<A> {
+    /// Remove and return the oldest element, if any.
+    pub fn try_pop(&mut self) -> Option<A> {
+        let shared = &*self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        // SAFETY: the slot at `head` was published by the producer's release
+        // store and will not be rewritten before ours below.
+        let val = unsafe { (*shared.slot(head)).assume_init_read() };
+        shared.head.store(shared.advance(head, 1), Ordering::Release);
+        Some(val)
+    }
+
+    /// Move the oldest elements into `out`, as many as are available and
+    /// fit, and return how many.
+    pub fn pop_slice(&mut self, out: &mut [A]) -> usize
+    where
+        A: Copy,
+    {
+        let shared = &*self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        let n = out.len().min(shared.len(head, tail));
+        let mut copied = 0;
+        for (idx, count) in shared.runs(head, n) {
+            // SAFETY: the `n` slots from `head` are live, as in `try_pop`.
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    shared.slots[idx].get() as *const A,
+                    out[copied..].as_mut_ptr(),
+                    count,
+                )
+            };
+            copied += count;
+        }
+        shared.head.store(shared.advance(head, n), Ordering::Release);
+        n
+    }
+
+    pub fn len(&self) -> usize {
+        len(&self.shared)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+}
+
+/// Number of elements at the time of the call; the other side may change
+/// it right away.
+fn len<A>(shared: &Shared<A>) -> usize {
+    let head = shared.head.load(Ordering::Acquire);
+    let tail = shared.tail.load(Ordering::Acquire);
+    shared.len(head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{assert_send_sync, DropCounter};
+    use std::cell::Cell;
+    use std::thread;
+    use std::vec::Vec;
+
+    #[test]
+    fn push_and_pop_across_wrap() {
+        let (mut tx, mut rx) = new(3);
+        for round in 0..5 {
+            assert_eq!(tx.try_push(round), Ok(()));
+            assert_eq!(tx.try_push(round + 10), Ok(()));
+            assert_eq!(rx.len(), 2);
+            assert_eq!(rx.try_pop(), Some(round));
+            assert_eq!(rx.try_pop(), Some(round + 10));
+            assert_eq!(rx.try_pop(), None);
+        }
+        tx.push_slice(&[1, 2, 3]);
+        assert!(tx.is_full());
+        assert_eq!(tx.try_push(4), Err(Full(4)));
+        assert_eq!(rx.try_pop(), Some(1));
+        assert_eq!(tx.try_push(4), Ok(()));
+    }
+
+    #[test]
+    fn split_keeps_existing_elements() {
+        let mut rb = crate::new(4);
+        rb.extend(0..6);
+        rb.pop_back();
+        let (mut tx, mut rx) = split(rb);
+        assert_eq!((tx.capacity(), tx.len()), (4, 3));
+        tx.try_push(6).unwrap();
+        assert!(tx.try_push(7).is_err());
+        let mut out = [0; 8];
+        assert_eq!(rx.pop_slice(&mut out), 4);
+        assert_eq!(out[..4], [2, 3, 4, 6]);
+    }
+
+    #[test]
+    fn slices_wrap_around() {
+        let (mut tx, mut rx) = new(5);
+        assert_eq!(tx.push_slice(&[1, 2, 3, 4]), 4);
+        let mut out = [0; 3];
+        assert_eq!(rx.pop_slice(&mut out), 3);
+        assert_eq!(out, [1, 2, 3]);
+        assert_eq!(tx.push_slice(&[5, 6, 7, 8, 9, 10]), 4);
+        let mut out = [0; 6];
+        assert_eq!(rx.pop_slice(&mut out), 5);
+        assert_eq!(out, [4, 5, 6, 7, 8, 0]);
+        assert!(rx.is_empty());
+    }
+
+
+    #[test]
+    fn dropping_both_halves_drops_remaining_elements() {
+        let drops = Cell::new(0);
+        let (mut tx, mut rx) = new(4);
+        for _ in 0..3 {
+            assert!(tx.try_push(DropCounter(&drops)).is_ok());
+        }
+        drop(rx.try_pop());
+        drop(tx);
+        assert_eq!(drops.get(), 1);
+        drop(rx);
+        assert_eq!(drops.get(), 3);
+    }
+
+    #[test]
+    fn stress_many_thread_pairs() {
+        const ITEMS: u64 = 100_000;
+        let pairs: Vec<_> = (0..8)
+            .map(|pair| {
+                let (mut tx, mut rx) = new::<u64>(16 + pair);
+                let producer = thread::spawn(move || {
+                    for i in 0..ITEMS {
+                        while tx.try_push(i).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                });
+                let consumer = thread::spawn(move || {
+                    let mut expected = 0;
+                    while expected < ITEMS {
+                        match rx.try_pop() {
+                            Some(i) => {
+                                assert_eq!(i, expected);
+                                expected += 1;
+                            }
+                            None => thread::yield_now(),
+                        }
+                    }
+                    assert!(rx.try_pop().is_none());
+                });
+                (producer, consumer)
+            })
+            .collect();
+        for (producer, consumer) in pairs {
+            producer.join().unwrap();
+            consumer.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn stress_slices_across_threads() {
+        const ITEMS: u32 = 200_000;
+        let (mut tx, mut rx) = new::<u32>(64);
+        let producer = thread::spawn(move || {
+            let vals: Vec<u32> = (0..ITEMS).collect();
+            let mut sent = 0;
+            while sent < vals.len() {
+                let end = (sent + 37).min(vals.len());
+                match tx.push_slice(&vals[sent..end]) {
+                    0 => thread::yield_now(),
+                    n => sent += n,
+                }
+            }
+        });
+        let mut out = [0; 29];
+        let mut expected = 0;
+        while expected < ITEMS {
+            let n = rx.pop_slice(&mut out);
+            if n == 0 {
+                thread::yield_now();
+            }
+            for &x in &out[..n] {
+                assert_eq!(x, expected);
+                expected += 1;
+            }
+        }
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn halves_are_send_and_sync() {
+        assert_send_sync::<Producer<Vec<u8>>>();
+        assert_send_sync::<Consumer<Vec<u8>>>();
+    }
+}
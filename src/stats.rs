@@ -0,0 +1,233 @@
+//! Running statistics over the last `N` samples.
+//!
+//! [`StatsRing`] keeps its samples in a [`RingBuffer`] and updates the sum,
+//! mean, variance, minimum and maximum on every push from the new sample
+//! and the one it evicts, instead of walking the whole window.
+
+use core::ops::Deref;
+
+use crate::RingBuffer;
+
+/// Numbers a [`StatsRing`] can hold.
+pub trait Sample: Copy + PartialOrd {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_sample {
+    ($($t:ty),*) => {
+        $(impl Sample for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        })*
+    };
+}
+
+impl_sample!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+/// Window of the last `capacity` samples with O(1) statistics.
+///
+/// The mean and variance use Welford's algorithm, extended to remove
+/// evicted samples.  The minimum and maximum come from monotonic queues of
+/// the samples that can still become the window's extreme, tagged with
+/// their sequence numbers so that evicted ones can be recognized.
+///
+/// Reading the samples themselves goes through `Deref`.
+pub struct StatsRing<A> {
+    samples: RingBuffer<A>,
+    sum: f64,
+    mean: f64,
+    /// Sum of squared differences from the mean.
+    m2: f64,
+    /// Increasing samples, the first one being the minimum.
+    mins: RingBuffer<(u64, A)>,
+    /// Decreasing samples, the first one being the maximum.
+    maxs: RingBuffer<(u64, A)>,
+}
+
+/// Create an empty window of `size` samples.  Panics if `size` is 0.
+pub fn new<A: Sample>(size: usize) -> StatsRing<A> {
+    StatsRing {
+        samples: crate::new(size),
+        sum: 0.0,
+        mean: 0.0,
+        m2: 0.0,
+        mins: crate::new(size),
+        maxs: crate::new(size),
+    }
+}
+
+impl<A: Sample> StatsRing<A> {
+    /// Add `val` as the newest sample, returning the oldest one if the
+    /// window was full.
+    pub fn push(&mut self, val: A) -> Option<A> {
+        let seq = self.samples.next_seq();
+        let evicted = self.samples.push(val);
+        let len = self.samples.len();
+        if let Some(old) = evicted {
+            self.remove(old, len - 1);
+        }
+        self.add(val, len);
+        while self.mins.back().is_some_and(|&(_, x)| x >= val) {
+            self.mins.pop_back();
+        }
+        self.mins.push((seq, val));
+        while self.maxs.back().is_some_and(|&(_, x)| x <= val) {
+            self.maxs.pop_back();
+        }
+        self.maxs.push((seq, val));
+        self.expire();
+        evicted
+    }
+
+    /// Remove and return the oldest sample.
+    pub fn pop_front(&mut self) -> Option<A> {
+        let val = self.samples.pop_front()?;
+        self.remove(val, self.samples.len());
+        self.expire();
+        Some(val)
+    }
+
+    /// Remove every sample.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.mins.clear();
+        self.maxs.clear();
+        (self.sum, self.mean, self.m2) = (0.0, 0.0, 0.0);
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then_some(self.mean)
+    }
+
+    /// Population variance of the samples.
+    pub fn variance(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.m2.max(0.0) / self.len() as f64)
+    }
+
+    /// Sample variance, dividing by `len - 1`; needs two samples.
+    pub fn sample_variance(&self) -> Option<f64> {
+        (self.len() > 1).then(|| self.m2.max(0.0) / (self.len() - 1) as f64)
+    }
+
+    /// Population standard deviation of the samples.
+    #[cfg(feature = "std")]
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    pub fn min(&self) -> Option<A> {
+        self.mins.front().map(|&(_, x)| x)
+    }
+
+    pub fn max(&self) -> Option<A> {
+        self.maxs.front().map(|&(_, x)| x)
+    }
+
+    /// Account for `val` joining the statistics, making `len` samples.
+    fn add(&mut self, val: A, len: usize) {
+        let x = val.to_f64();
+        let delta = x - self.mean;
+        self.sum += x;
+        self.mean += delta / len as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Account for `val` leaving the statistics, leaving `len` samples.
+    fn remove(&mut self, val: A, len: usize) {
+        let x = val.to_f64();
+        if len == 0 {
+            (self.sum, self.mean, self.m2) = (0.0, 0.0, 0.0);
+            return;
+        }
+        let delta = x - self.mean;
+        self.sum -= x;
+        self.mean -= delta / len as f64;
+        self.m2 -= delta * (x - self.mean);
+    }
+
+    /// Drop the queued extremes that left the window.
+    fn expire(&mut self) {
+        let first = self.samples.first_seq().unwrap_or(self.samples.next_seq());
+        while self.mins.front().is_some_and(|&(seq, _)| seq < first) {
+            self.mins.pop_front();
+        }
+        while self.maxs.front().is_some_and(|&(seq, _)| seq < first) {
+            self.maxs.pop_front();
+        }
+    }
+}
+
+impl<A> Deref for StatsRing<A> {
+    type Target = RingBuffer<A>;
+
+    fn deref(&self) -> &RingBuffer<A> {
+        &self.samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{assert_close, lcg};
+
+    /// Deterministic pseudo-random samples.
+    fn samples(count: usize) -> Vec<i64> {
+        lcg(12345).take(count).map(|x| (x >> 40) as i64 % 1000 - 500).collect()
+    }
+
+    #[test]
+    fn empty_window_has_no_statistics() {
+        let stats = new::<f64>(3);
+        assert_eq!(stats.sum(), 0.0);
+        assert_eq!((stats.mean(), stats.variance()), (None, None));
+        assert_eq!((stats.min(), stats.max()), (None, None));
+    }
+
+    #[test]
+    fn statistics_follow_the_window() {
+        let mut stats = new(3);
+        assert_eq!(stats.push(2.0), None);
+        assert_eq!(stats.sample_variance(), None);
+        stats.push(4.0);
+        stats.push(9.0);
+        assert_eq!(stats.push(1.0), Some(2.0));
+        assert_eq!(stats.sum(), 14.0);
+        assert_close(stats.mean(), 14.0 / 3.0);
+        assert_close(stats.variance(), 98.0 / 9.0);
+        assert_close(stats.sample_variance(), 98.0 / 6.0);
+        #[cfg(feature = "std")]
+        assert_close(stats.std_dev(), (98.0f64 / 9.0).sqrt());
+        assert_eq!((stats.min(), stats.max()), (Some(1.0), Some(9.0)));
+        assert_eq!(stats.pop_front(), Some(4.0));
+        stats.pop_front();
+        assert_eq!((stats.min(), stats.max()), (Some(1.0), Some(1.0)));
+        assert_close(stats.variance(), 0.0);
+        stats.clear();
+        assert_eq!(stats.mean(), None);
+    }
+
+    #[test]
+    fn matches_recomputing_from_scratch() {
+        let mut stats = new(7);
+        for (i, x) in samples(500).into_iter().enumerate() {
+            stats.push(x);
+            if i % 50 == 49 {
+                stats.pop_front();
+            }
+            let window: Vec<f64> = stats.iter().map(|&x| x as f64).collect();
+            let n = window.len() as f64;
+            let mean = window.iter().sum::<f64>() / n;
+            let variance = window.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n;
+            assert_eq!(stats.sum(), window.iter().sum::<f64>());
+            assert_close(stats.mean(), mean);
+            assert_close(stats.variance(), variance);
+            assert_eq!(stats.min(), stats.iter().copied().min());
+            assert_eq!(stats.max(), stats.iter().copied().max());
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use core::mem::MaybeUninit;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Where a [`RingBuffer`](crate::RingBuffer) keeps its slots: a heap
+/// allocated `Vec` or an inline array.
+///
+/// This trait is sealed; the ring buffer relies on the number of slots never
+/// changing behind its back.
+pub trait Storage<A>: sealed::Slots<A> {}
+
+#[cfg(feature = "alloc")]
+impl<A> Storage<A> for Vec<MaybeUninit<A>> {}
+
+impl<A, const N: usize> Storage<A> for [MaybeUninit<A>; N] {}
+
+pub(crate) mod sealed {
+    use core::mem::MaybeUninit;
+
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    pub trait Slots<A> {
+        /// One sequence number per slot, stored alongside the elements.
+        type Seqs: AsRef<[u64]> + AsMut<[u64]>;
+
+        /// Fresh storage with `capacity` uninitialized slots.  Arrays always
+        /// have their own length and ignore it.
+        fn uninit(capacity: usize) -> Self;
+
+        /// Sequence numbers for `capacity` slots, sized like `uninit`.
+        fn seqs(capacity: usize) -> Self::Seqs;
+
+        /// Like `seqs` but returns `None` if the allocation fails.
+        fn try_seqs(capacity: usize) -> Option<Self::Seqs>;
+
+        fn slots(&self) -> &[MaybeUninit<A>];
+
+        fn slots_mut(&mut self) -> &mut [MaybeUninit<A>];
+    }
+
+    #[cfg(feature = "alloc")]
+    impl<A> Slots<A> for Vec<MaybeUninit<A>> {
+        type Seqs = Vec<u64>;
+
+        fn uninit(capacity: usize) -> Self {
+            let mut slots = Vec::with_capacity(capacity);
+            slots.resize_with(capacity, MaybeUninit::uninit);
+            slots
+        }
+
+        fn seqs(capacity: usize) -> Vec<u64> {
+            alloc::vec![0; capacity]
+        }
+
+        fn try_seqs(capacity: usize) -> Option<Vec<u64>> {
+            let mut seqs = Vec::new();
+            seqs.try_reserve_exact(capacity).ok()?;
+            seqs.resize(capacity, 0);
+            Some(seqs)
+        }
+
+        fn slots(&self) -> &[MaybeUninit<A>] {
+            self
+        }
+
+        fn slots_mut(&mut self) -> &mut [MaybeUninit<A>] {
+            self
+        }
+    }
+
+    impl<A, const N: usize> Slots<A> for [MaybeUninit<A>; N] {
+        type Seqs = [u64; N];
+
+        fn uninit(_capacity: usize) -> Self {
+            [const { MaybeUninit::uninit() }; N]
+        }
+
+        fn seqs(_capacity: usize) -> [u64; N] {
+            [0; N]
+        }
+
+        fn try_seqs(_capacity: usize) -> Option<[u64; N]> {
+            Some([0; N])
+        }
+
+        fn slots(&self) -> &[MaybeUninit<A>] {
+            self
+        }
+
+        fn slots_mut(&mut self) -> &mut [MaybeUninit<A>] {
+            self
+        }
+    }
+}
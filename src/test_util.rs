@@ -0,0 +1,46 @@
+//! Fixtures shared by the test modules.
+
+use std::cell::Cell;
+
+#[cfg(feature = "alloc")]
+use crate::RingBuffer;
+
+/// A buffer of capacity `capacity` after pushing `0..pushes`, so that it
+/// wraps around once `pushes` exceeds the capacity.
+#[cfg(feature = "alloc")]
+pub(crate) fn wrapped(capacity: usize, pushes: i32) -> RingBuffer<i32> {
+    let mut rb = crate::new(capacity);
+    rb.extend(0..pushes);
+    rb
+}
+
+/// Counts how many times it has been dropped.
+pub(crate) struct DropCounter<'a>(pub(crate) &'a Cell<usize>);
+
+impl Drop for DropCounter<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+/// Fails to compile unless `T` is `Send` and `Sync`.
+#[cfg(feature = "alloc")]
+pub(crate) fn assert_send_sync<T: Send + Sync>() {}
+
+/// Deterministic pseudo-random numbers from a linear congruential generator.
+#[cfg(feature = "alloc")]
+pub(crate) fn lcg(seed: u64) -> impl Iterator<Item = u64> {
+    let mut x = seed;
+    std::iter::repeat_with(move || {
+        x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        x
+    })
+}
+
+/// Assert that `actual` holds `expected` up to rounding errors.
+#[cfg(feature = "alloc")]
+pub(crate) fn assert_close(actual: Option<f64>, expected: f64) {
+    let actual = actual.unwrap();
+    let tolerance = 1e-9 * expected.abs().max(1.0);
+    assert!((actual - expected).abs() < tolerance, "{} != {}", actual, expected);
+}
@@ -0,0 +1,141 @@
+//! Ring buffer of the elements pushed within a recent time span.
+//!
+//! [`TimedRingBuffer`] timestamps every element with its [`Clock`] and
+//! drops the ones older than its maximum age.  Its capacity still bounds
+//! memory: when it is full, pushing overwrites the oldest element even if
+//! it has not expired.
+
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, SystemClock};
+use crate::RingBuffer;
+
+/// Elements pushed less than `max_age` ago, at most `capacity` of them.
+///
+/// Expired elements are dropped on every [`push`](TimedRingBuffer::push)
+/// and by [`expire`](TimedRingBuffer::expire); in between, the buffer may
+/// still hold some.  Reading the `(Instant, A)` entries, oldest first,
+/// goes through `Deref`.
+pub struct TimedRingBuffer<A, C: Clock = SystemClock> {
+    entries: RingBuffer<(Instant, A)>,
+    max_age: Duration,
+    clock: C,
+}
+
+/// Create an empty buffer keeping up to `size` elements for `max_age`,
+/// timed by the system clock.  Panics if `size` is 0.
+pub fn new<A>(size: usize, max_age: Duration) -> TimedRingBuffer<A> {
+    with_clock(size, max_age, SystemClock)
+}
+
+/// Like [`new`] but timed by `clock`.
+pub fn with_clock<A, C: Clock>(size: usize, max_age: Duration, clock: C) -> TimedRingBuffer<A, C> {
+    TimedRingBuffer { entries: crate::new(size), max_age, clock }
+}
+
+impl<A, C: Clock> TimedRingBuffer<A, C> {
+    /// Drop the expired elements, then append `val` stamped with the
+    /// current time.
+    ///
+    /// Returns the oldest element if the buffer was still full, which
+    /// means it was overwritten before expiring.
+    pub fn push(&mut self, val: A) -> Option<A> {
+        let now = self.clock.now();
+        self.expire(now);
+        self.entries.push((now, val)).map(|(_, val)| val)
+    }
+
+    /// Drop the elements that are at least `max_age` old at `now` and
+    /// return how many there were.
+    pub fn expire(&mut self, now: Instant) -> usize {
+        let len = self.entries.len();
+        while self
+            .entries
+            .front()
+            .is_some_and(|&(time, _)| now.saturating_duration_since(time) >= self.max_age)
+        {
+            self.entries.pop_front();
+        }
+        len - self.entries.len()
+    }
+
+    /// [`expire`](TimedRingBuffer::expire) at the clock's current time.
+    pub fn expire_now(&mut self) -> usize {
+        self.expire(self.clock.now())
+    }
+
+    /// Iterate over the elements, oldest first, without their timestamps.
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &A> + ExactSizeIterator {
+        self.entries.iter().map(|(_, val)| val)
+    }
+
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+}
+
+impl<A, C: Clock> Deref for TimedRingBuffer<A, C> {
+    type Target = RingBuffer<(Instant, A)>;
+
+    fn deref(&self) -> &RingBuffer<(Instant, A)> {
+        &self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn pushing_drops_expired_elements() {
+        let clock = ManualClock::new();
+        let mut events = with_clock(10, 30 * SECOND, clock.clone());
+        events.push("a");
+        clock.advance(10 * SECOND);
+        events.push("b");
+        clock.advance(19 * SECOND);
+        events.push("c");
+        assert_eq!(events.values().collect::<Vec<_>>(), [&"a", &"b", &"c"]);
+        clock.advance(SECOND);
+        events.push("d");
+        assert_eq!(events.values().collect::<Vec<_>>(), [&"b", &"c", &"d"]);
+        assert_eq!(events.front().map(|&(time, _)| clock.now() - time), Some(20 * SECOND));
+    }
+
+    #[test]
+    fn explicit_expiry() {
+        let clock = ManualClock::new();
+        let mut events = with_clock(10, 5 * SECOND, clock.clone());
+        for i in 0..4 {
+            events.push(i);
+            clock.advance(2 * SECOND);
+        }
+        assert_eq!(events.len(), 3);
+        assert_eq!(events.expire_now(), 1);
+        assert_eq!(events.values().copied().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(events.expire(clock.now() + 10 * SECOND), 2);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn capacity_is_a_hard_limit() {
+        let clock = ManualClock::new();
+        let mut events = with_clock(3, 60 * SECOND, clock);
+        for i in 0..3 {
+            assert_eq!(events.push(i), None);
+        }
+        assert_eq!(events.push(3), Some(0));
+        assert_eq!(events.values().copied().collect::<Vec<_>>(), [1, 2, 3]);
+        let mut system = new(3, 60 * SECOND);
+        system.push(());
+        assert_eq!(system.expire_now(), 0);
+    }
+}
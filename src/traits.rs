@@ -0,0 +1,295 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Index, IndexMut};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{new_array, ArrayRingBuffer, FrozenRingBuffer, RingBuffer, RingBufferView, Storage};
+
+impl<A: fmt::Debug, S: Storage<A>> fmt::Debug for RingBuffer<A, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// The clone keeps the capacity, overflow policy and sequence numbers of
+/// the original but has no eviction sink.
+impl<A: Clone, S: Storage<A>> Clone for RingBuffer<A, S> {
+    fn clone(&self) -> Self {
+        let mut ring_buffer = RingBuffer::from_slots(S::uninit(self.capacity));
+        ring_buffer.policy = self.policy;
+        ring_buffer.extend(self.iter().cloned());
+        for idx in 0..self.len() {
+            ring_buffer.seqs.as_mut()[idx] = self.seqs.as_ref()[self.physical(idx)];
+        }
+        ring_buffer.next_seq = self.next_seq;
+        ring_buffer
+    }
+}
+
+/// Buffers compare their elements oldest first: neither the capacity nor
+/// where the elements sit in the underlying storage matter.
+impl<A: PartialEq, S: Storage<A>> PartialEq for RingBuffer<A, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<A: Eq, S: Storage<A>> Eq for RingBuffer<A, S> {}
+
+impl<A: Hash, S: Storage<A>> Hash for RingBuffer<A, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        self.iter().for_each(|x| x.hash(state));
+    }
+}
+
+impl<A: PartialOrd, S: Storage<A>> PartialOrd for RingBuffer<A, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<A: Ord, S: Storage<A>> Ord for RingBuffer<A, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<A, S: Storage<A>> Index<usize> for RingBuffer<A, S> {
+    type Output = A;
+
+    fn index(&self, idx: usize) -> &A {
+        let len = self.len();
+        self.get(idx).unwrap_or_else(|| {
+            panic!("index {} out of bounds for ring buffer of length {}", idx, len)
+        })
+    }
+}
+
+impl<A, S: Storage<A>> IndexMut<usize> for RingBuffer<A, S> {
+    fn index_mut(&mut self, idx: usize) -> &mut A {
+        let len = self.len();
+        self.get_mut(idx).unwrap_or_else(|| {
+            panic!("index {} out of bounds for ring buffer of length {}", idx, len)
+        })
+    }
+}
+
+impl<A, S: Storage<A>> Index<usize> for FrozenRingBuffer<A, S> {
+    type Output = A;
+
+    fn index(&self, idx: usize) -> &A {
+        &self.ring_buffer[idx]
+    }
+}
+
+impl<A: fmt::Debug, S: Storage<A>> fmt::Debug for FrozenRingBuffer<A, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrozenRingBuffer").field("ring_buffer", &self.ring_buffer).finish()
+    }
+}
+
+impl<A: Clone, S: Storage<A>> Clone for FrozenRingBuffer<A, S> {
+    fn clone(&self) -> Self {
+        FrozenRingBuffer { ring_buffer: self.ring_buffer.clone() }
+    }
+}
+
+impl<A: PartialEq, S: Storage<A>> PartialEq for FrozenRingBuffer<A, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ring_buffer == other.ring_buffer
+    }
+}
+
+impl<A: Eq, S: Storage<A>> Eq for FrozenRingBuffer<A, S> {}
+
+impl<A: Hash, S: Storage<A>> Hash for FrozenRingBuffer<A, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ring_buffer.hash(state)
+    }
+}
+
+impl<A: PartialOrd, S: Storage<A>> PartialOrd for FrozenRingBuffer<A, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.ring_buffer.partial_cmp(&other.ring_buffer)
+    }
+}
+
+impl<A: Ord, S: Storage<A>> Ord for FrozenRingBuffer<A, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ring_buffer.cmp(&other.ring_buffer)
+    }
+}
+
+impl<A: fmt::Debug> fmt::Debug for RingBufferView<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Views compare their elements like slices, wherever the buffer wraps.
+impl<A: PartialEq> PartialEq for RingBufferView<'_, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<A: Eq> Eq for RingBufferView<'_, A> {}
+
+impl<A: Hash> Hash for RingBufferView<'_, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        self.iter().for_each(|x| x.hash(state));
+    }
+}
+
+impl<A: PartialOrd> PartialOrd for RingBufferView<'_, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<A: Ord> Ord for RingBufferView<'_, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<A> Index<usize> for RingBufferView<'_, A> {
+    type Output = A;
+
+    fn index(&self, idx: usize) -> &A {
+        let len = self.len();
+        self.at(idx).unwrap_or_else(|| {
+            panic!("index {} out of bounds for ring buffer of length {}", idx, len)
+        })
+    }
+}
+
+impl<A, const N: usize> Default for ArrayRingBuffer<A, N> {
+    fn default() -> Self {
+        new_array()
+    }
+}
+
+/// Pushes every element in turn, so a full buffer handles each one
+/// according to its [`OverflowPolicy`](crate::OverflowPolicy).
+impl<A, S: Storage<A>> Extend<A> for RingBuffer<A, S> {
+    fn extend<I: IntoIterator<Item = A>>(&mut self, iter: I) {
+        for val in iter {
+            self.push(val);
+        }
+    }
+}
+
+impl<'a, A: Copy + 'a, S: Storage<A>> Extend<&'a A> for RingBuffer<A, S> {
+    fn extend<I: IntoIterator<Item = &'a A>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+/// Collects into a buffer that is exactly full, or of capacity 1 if the
+/// iterator is empty.
+#[cfg(feature = "alloc")]
+impl<A> FromIterator<A> for RingBuffer<A> {
+    fn from_iter<I: IntoIterator<Item = A>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<A>>())
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::{freeze, new, RingBuffer};
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of<T: Hash>(x: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        x.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn debug_lists_elements_oldest_first() {
+        let rb: RingBuffer<i32> = (0..5).collect();
+        assert_eq!(format!("{:?}", rb), "[0, 1, 2, 3, 4]");
+        let mut rb = new::<i32>(2);
+        rb.extend(&[1, 2, 3]);
+        assert_eq!(format!("{:?}", freeze(rb)), "FrozenRingBuffer { ring_buffer: [2, 3] }");
+    }
+
+    #[test]
+    fn equality_ignores_physical_layout() {
+        let mut wrapped = new(3);
+        wrapped.extend([0, 1, 2, 3, 4]);
+        let mut straight = new(3);
+        straight.extend([2, 3, 4]);
+        assert_ne!((wrapped.start, wrapped.end), (straight.start, straight.end));
+        assert_eq!(wrapped, straight);
+        assert_eq!(hash_of(&wrapped), hash_of(&straight));
+        let popped = {
+            let mut rb = new(5);
+            rb.extend([1, 2, 3, 4]);
+            rb.pop_front();
+            rb
+        };
+        assert_eq!(popped, straight);
+        straight.pop_back();
+        assert_ne!(wrapped, straight);
+        assert!(straight < wrapped);
+        assert_eq!(wrapped.view(), popped.view());
+        assert_eq!(hash_of(&wrapped.view()), hash_of(&popped.view()));
+        assert!(wrapped.slice(1..) > popped.slice(..2));
+        assert_eq!(freeze(wrapped), freeze(popped));
+    }
+
+    #[test]
+    fn ordering_is_lexicographic() {
+        let a: RingBuffer<i32> = [1, 2, 3].into_iter().collect();
+        let b: RingBuffer<i32> = [1, 3].into_iter().collect();
+        assert!(a < b);
+        assert_eq!(a.cmp(&a.clone()), std::cmp::Ordering::Equal);
+        assert!(freeze(b) > freeze(a));
+    }
+
+    #[test]
+    fn clone_keeps_capacity_and_contents() {
+        let mut rb = new(4);
+        rb.extend(0..6);
+        let mut copy = rb.clone();
+        assert_eq!(copy.capacity(), 4);
+        assert_eq!(copy, rb);
+        copy.push(6);
+        assert_eq!(copy[0], 3);
+        assert_eq!(rb[0], 2);
+    }
+
+    #[test]
+    fn index_uses_logical_positions() {
+        let mut rb = new(3);
+        rb.extend(0..5);
+        assert_eq!((rb[0], rb[1], rb[2]), (2, 3, 4));
+        rb[1] = 30;
+        assert_eq!(freeze(rb)[1], 30);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of bounds")]
+    fn index_out_of_bounds_panics() {
+        let rb: RingBuffer<i32> = (0..3).collect();
+        let _ = rb[3];
+    }
+
+    #[test]
+    fn from_iterator_sizes_capacity_to_contents() {
+        let rb: RingBuffer<i32> = (0..3).collect();
+        assert_eq!(rb.capacity(), 3);
+        assert!(rb.is_full());
+        let empty: RingBuffer<i32> = std::iter::empty().collect();
+        assert_eq!(empty.capacity(), 1);
+        assert!(empty.is_empty());
+    }
+}
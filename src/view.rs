@@ -0,0 +1,189 @@
+use core::ops::RangeBounds;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::bounds;
+use crate::iter::{Chunks, Iter, Windows};
+
+/// Borrowed read-only view of consecutive elements of a ring buffer.
+///
+/// Behaves like a slice whose elements may be split in two parts where the
+/// buffer wraps around, see [`RingBuffer::view`](crate::RingBuffer::view).
+/// Logical index 0 is the oldest element in the view.
+pub struct RingBufferView<'a, A> {
+    head: &'a [A],
+    tail: &'a [A],
+}
+
+impl<'a, A> RingBufferView<'a, A> {
+    /// `tail` continues `head`; it is moved forward if `head` is empty.
+    pub(crate) fn new(head: &'a [A], tail: &'a [A]) -> Self {
+        if head.is_empty() {
+            RingBufferView { head: tail, tail: &[] }
+        } else {
+            RingBufferView { head, tail }
+        }
+    }
+
+    /// The element at logical index `idx`.
+    pub fn at(&self, idx: usize) -> Option<&'a A> {
+        match idx.checked_sub(self.head.len()) {
+            None => self.head.get(idx),
+            Some(idx) => self.tail.get(idx),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.head.len() + self.tail.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_empty()
+    }
+
+    /// The oldest element.
+    pub fn first(&self) -> Option<&'a A> {
+        self.head.first()
+    }
+
+    /// The newest element.
+    pub fn last(&self) -> Option<&'a A> {
+        self.tail.last().or_else(|| self.head.last())
+    }
+
+    /// Iterate over the elements from oldest to newest.
+    pub fn iter(&self) -> Iter<'a, A> {
+        Iter { head: self.head.iter(), tail: self.tail.iter() }
+    }
+
+    /// The elements as two slices, the second one empty unless the view
+    /// spans the point where the buffer wraps around.
+    pub fn as_slices(&self) -> (&'a [A], &'a [A]) {
+        (self.head, self.tail)
+    }
+
+    /// The elements in the logical `range`.
+    ///
+    /// Panics if the range is out of bounds or decreasing.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> Self {
+        let range = match bounds(range, self.len()) {
+            Ok(range) => range,
+            Err(err) => panic!("{}", err),
+        };
+        let split = self.head.len();
+        let head = &self.head[range.start.min(split)..range.end.min(split)];
+        let tail = &self.tail[range.start.saturating_sub(split)..range.end.saturating_sub(split)];
+        RingBufferView::new(head, tail)
+    }
+
+    /// Divide the view in two at logical index `mid`.
+    ///
+    /// Panics if `mid > len`.
+    pub fn split_at(&self, mid: usize) -> (Self, Self) {
+        (self.slice(..mid), self.slice(mid..))
+    }
+
+    /// Iterate over all overlapping sub-views of `size` elements, oldest
+    /// first.  Panics if `size` is 0.
+    pub fn windows(&self, size: usize) -> Windows<'a, A> {
+        assert!(size > 0, "window size must be greater than zero");
+        Windows { view: *self, size }
+    }
+
+    /// Iterate over non-overlapping sub-views of `size` elements, oldest
+    /// first; the last one is shorter if `size` does not divide the length.
+    /// Panics if `size` is 0.
+    pub fn chunks(&self, size: usize) -> Chunks<'a, A> {
+        assert!(size > 0, "chunk size must be greater than zero");
+        Chunks { view: *self, size }
+    }
+
+    /// Copy the elements into a `Vec` ordered oldest first.
+    #[cfg(feature = "alloc")]
+    pub fn to_vec(&self) -> Vec<A>
+    where
+        A: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<A> Clone for RingBufferView<'_, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A> Copy for RingBufferView<'_, A> {}
+
+impl<'a, A> IntoIterator for RingBufferView<'a, A> {
+    type Item = &'a A;
+    type IntoIter = Iter<'a, A>;
+
+    fn into_iter(self) -> Iter<'a, A> {
+        self.iter()
+    }
+}
+
+impl<'a, A> IntoIterator for &RingBufferView<'a, A> {
+    type Item = &'a A;
+    type IntoIter = Iter<'a, A>;
+
+    fn into_iter(self) -> Iter<'a, A> {
+        self.iter()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::test_util::wrapped;
+
+    #[test]
+    fn view_borrows_without_freezing() {
+        let mut rb = wrapped(5, 8);
+        let view = rb.view();
+        assert_eq!(view.len(), 5);
+        assert_eq!((view.first(), view.last()), (Some(&3), Some(&7)));
+        assert_eq!(view.at(3), Some(&6));
+        assert_eq!(view.at(5), None);
+        assert_eq!(view.as_slices(), (&[3, 4][..], &[5, 6, 7][..]));
+        assert_eq!(view.iter().rev().copied().collect::<Vec<_>>(), [7, 6, 5, 4, 3]);
+        rb.push(8);
+        assert_eq!(rb.view().to_vec(), [4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn slices_across_the_wrap() {
+        let rb = wrapped(5, 8);
+        assert_eq!(rb.slice(1..4).to_vec(), [4, 5, 6]);
+        assert_eq!(rb.slice(2..).as_slices(), (&[5, 6, 7][..], &[][..]));
+        assert_eq!(rb.slice(..=1).as_slices(), (&[3, 4][..], &[][..]));
+        assert!(rb.slice(5..).is_empty());
+        let (left, right) = rb.view().split_at(3);
+        assert_eq!((left.to_vec(), right.to_vec()), (vec![3, 4, 5], vec![6, 7]));
+        assert_eq!(rb.slice(1..).slice(1..3).to_vec(), [5, 6]);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 6 out of bounds for ring buffer of length 5")]
+    fn slice_out_of_bounds_panics() {
+        wrapped(5, 8).slice(2..6);
+    }
+
+    #[test]
+    fn windows_and_chunks() {
+        let rb = wrapped(5, 8);
+        let windows: Vec<_> = rb.view().windows(3).map(|w| w.to_vec()).collect();
+        assert_eq!(windows, [[3, 4, 5], [4, 5, 6], [5, 6, 7]]);
+        assert_eq!(rb.view().windows(3).len(), 3);
+        assert_eq!(rb.view().windows(6).next(), None);
+        assert_eq!(rb.view().windows(2).next_back().unwrap().to_vec(), [6, 7]);
+        let chunks: Vec<_> = rb.view().chunks(2).map(|c| c.to_vec()).collect();
+        assert_eq!(chunks, [vec![3, 4], vec![5, 6], vec![7]]);
+        assert_eq!(rb.view().chunks(2).len(), 3);
+        assert_eq!(rb.view().chunks(2).next_back().unwrap().to_vec(), [7]);
+        let sums: Vec<i32> = rb.view().windows(2).map(|w| w.iter().sum()).collect();
+        assert_eq!(sums, [7, 9, 11, 13]);
+    }
+}